@@ -13,6 +13,10 @@ mod subscriptions {
 
     pub const BLOCKS_PER_WEEK: u32 = 10 * 1; // 3600 * 24 * 7;
     pub const BLOCKS_PER_MONTH: u32 = 3600 * 24 * 7 * 30;
+    /// Maximum number of external channel handles of a single subscription
+    pub const MAX_CHANNEL_HANDLES: u32 = 8;
+    /// Maximum number of accounts watched by a single subscription besides the subscribed account
+    pub const MAX_WATCHED_ACCOUNTS: u32 = 8;
 
     /// Defines subscription payment interval
     #[derive(Debug, Clone, Copy, PartialEq, scale::Encode, scale::Decode)]
@@ -25,6 +29,49 @@ mod subscriptions {
         Month,
     }
 
+    /// Kinds of on-chain events the subscriber is notified about
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum EventKind {
+        /// Tokens transferred to the subscribed account
+        TransferIn,
+        /// Tokens transferred from the subscribed account
+        TransferOut,
+        /// Staking reward paid to the subscribed stash account
+        Rewarded,
+        /// Stake of the subscribed account slashed
+        Slashed,
+        /// Tokens of the subscribed account bonded for staking
+        Bonded,
+        /// Tokens of the subscribed account unbonded
+        Unbonded,
+        /// Unbonded tokens of the subscribed account withdrawn
+        Withdrawn,
+        /// Rewards, bonds, withdrawals and state changes of the nomination pool the subscribed account is a member of
+        NominationPools,
+        /// Ban of the subscribed validator or its inclusion in or removal from the set of validators
+        Committee,
+        /// Events of the contracts watched by the notification service, with a field matching the subscribed account
+        ContractEvents,
+        /// Transfers and approvals of the PSP22 tokens watched by the notification service
+        Psp22,
+        /// Events listed by the notification service operator, with an account field matching the subscribed account
+        Dynamic,
+    }
+
+    impl EventKind {
+        /// Returns true if the events of this kind carry an amount, so a minimum amount can be set for them
+        pub fn has_amount(&self) -> bool {
+            !matches!(
+                self,
+                EventKind::Committee | EventKind::ContractEvents | EventKind::Dynamic
+            )
+        }
+    }
+
     /// Subscription data
     #[derive(Debug, Clone, scale::Encode, scale::Decode)]
     #[cfg_attr(
@@ -45,8 +92,18 @@ mod subscriptions {
         registered_at: BlockNumber,
         /// Last payment at
         last_payment_at: BlockNumber,
-        /// External channel handle specific for the subscription, e.g. Telegram channel ID
-        external_channel_handle: String,
+        /// External channel handles specific for the subscription, e.g. Telegram channel ID and webhook URL
+        external_channel_handles: Vec<String>,
+        /// Kinds of on-chain events the subscriber is notified about
+        event_kinds: Vec<EventKind>,
+        /// Minimum amounts per event kind, events with lower amounts are not notified
+        min_amounts: Vec<(EventKind, Balance)>,
+        /// Accounts watched besides the subscribed account, e.g. controller and nominator accounts of a validator
+        watched_accounts: Vec<AccountId>,
+        /// Who paid for the subscription. Remaining tokens are returned to this account on cancellation by default
+        payer: AccountId,
+        /// Paused at block. Notifications are not sent and payments are not settled while paused
+        paused_at: Option<BlockNumber>,
     }
 
     /// Active subscription attributes to be exposed externally
@@ -56,8 +113,20 @@ mod subscriptions {
         /// Who registerred new subscription. Events published for this account will result in notifications
         for_account: AccountId,
 
-        /// A handle (e.g. chat_id) associated with the user's subscription
-        external_channel_handle: Vec<u8>,
+        /// Handles (e.g. chat_id) associated with the user's subscription. Notifications are sent to each of them
+        external_channel_handles: Vec<Vec<u8>>,
+
+        /// Kinds of on-chain events the subscriber is notified about
+        event_kinds: Vec<EventKind>,
+
+        /// Minimum amounts per event kind, events with lower amounts are not notified
+        min_amounts: Vec<(EventKind, Balance)>,
+
+        /// Accounts watched besides the subscribed account. Events published for them also result in notifications
+        watched_accounts: Vec<AccountId>,
+
+        /// Whether the subscription is paused. Paused subscriptions do not result in notifications
+        paused: bool,
     }
 
     /// Defines the storage layout of this smart contract.
@@ -72,6 +141,8 @@ mod subscriptions {
         subscriptions: Mapping<AccountId, Subscription>,
         /// List of active subscriptions
         active_subscriptions: Vec<AccountId>,
+        /// Accounts allowed by the beneficiaries to register a subscription on their behalf
+        approved_payers: Mapping<AccountId, AccountId>,
     }
 
     /// Errors returned by this smart contract
@@ -96,6 +167,32 @@ mod subscriptions {
         InconsistentSubscriptionData(AccountId),
         /// Ink! error can be converted to this smart contract errors
         InkEnvFailure(String),
+        /// Returned when more than `MAX_CHANNEL_HANDLES` channel handles provided
+        TooManyChannelHandles(u32),
+        /// Returned when the same channel handle provided more than once
+        DuplicatedChannelHandle(String),
+        /// Returned when no event kinds provided
+        MissingEventKinds,
+        /// Returned when the same event kind provided more than once
+        DuplicatedEventKind(EventKind),
+        /// Returned when the subscription would watch more than `MAX_WATCHED_ACCOUNTS` accounts
+        TooManyWatchedAccounts(u32),
+        /// Returned when the account is already watched by the subscription
+        AlreadyWatched(AccountId),
+        /// Returned when the account is not watched by the subscription
+        NotWatched(AccountId),
+        /// Returned when the channel handle is not associated with the subscription
+        ChannelHandleNotFound(String),
+        /// Returned when the subscription is already paused
+        AlreadyPaused(AccountId),
+        /// Returned when the subscription is not paused
+        NotPaused(AccountId),
+        /// Returned when the caller has not been approved by the beneficiary to pay for its subscription
+        PayerNotApproved(AccountId),
+        /// Returned when the number of payment intervals or their price exceeds the supported range
+        ArithmeticOverflow,
+        /// Returned when a minimum amount is provided for the event kind whose events carry no amount
+        MinAmountNotSupported(EventKind),
     }
 
     /// Converts ink::env::Error to this smart contract error
@@ -112,8 +209,105 @@ mod subscriptions {
         #[ink(topic)]
         for_account: AccountId,
 
-        /// A handle (e.g. chat_id) associated with the user's subscription
-        external_channel_handle: Vec<u8>,
+        /// Handles (e.g. chat_id) associated with the user's subscription. Notifications are sent to each of them
+        external_channel_handles: Vec<Vec<u8>>,
+
+        /// Kinds of on-chain events the subscriber is notified about
+        event_kinds: Vec<EventKind>,
+
+        /// Who paid for the subscription, the same as `for_account` unless subscribed on behalf of another account
+        #[ink(topic)]
+        payer: AccountId,
+    }
+
+    /// Event emitted when the subscriber pays for more intervals of the subscription
+    #[ink(event)]
+    pub struct SubscriptionExtended {
+        /// Whose subscription was extended
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// Number of intervals added to the subscription
+        intervals: u32,
+
+        /// Number of declared payment intervals after the extension
+        declared_payment_intervals: u32,
+    }
+
+    /// Event emitted when the subscriber replaces one of the channel handles
+    #[ink(event)]
+    pub struct ChannelHandleUpdated {
+        /// Who updated the subscription
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// Replaced channel handle
+        old_handle: Vec<u8>,
+
+        /// Channel handle notifications are sent to from now on
+        new_handle: Vec<u8>,
+    }
+
+    /// Event emitted when the subscriber changes the kinds of events they are notified about
+    #[ink(event)]
+    pub struct EventKindsUpdated {
+        /// Who updated the subscription
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// New kinds of on-chain events the subscriber is notified about
+        event_kinds: Vec<EventKind>,
+    }
+
+    /// Event emitted when the subscriber changes the minimum amounts of the notified events
+    #[ink(event)]
+    pub struct MinAmountsUpdated {
+        /// Who updated the subscription
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// New minimum amounts per event kind
+        min_amounts: Vec<(EventKind, Balance)>,
+    }
+
+    /// Event emitted when the subscriber starts watching another account
+    #[ink(event)]
+    pub struct WatchedAccountAdded {
+        /// Who updated the subscription
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// Account watched by the subscription
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when the subscriber stops watching an account
+    #[ink(event)]
+    pub struct WatchedAccountRemoved {
+        /// Who updated the subscription
+        #[ink(topic)]
+        for_account: AccountId,
+
+        /// Account no longer watched by the subscription
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when the subscriber pauses notifications
+    #[ink(event)]
+    pub struct SubscriptionPaused {
+        /// Whose subscription was paused
+        #[ink(topic)]
+        for_account: AccountId,
+    }
+
+    /// Event emitted when the subscriber resumes notifications
+    #[ink(event)]
+    pub struct SubscriptionResumed {
+        /// Whose subscription was resumed
+        #[ink(topic)]
+        for_account: AccountId,
     }
 
     /// Event emitted on subscription cancellation
@@ -143,6 +337,7 @@ mod subscriptions {
                 price_per_block,
                 subscriptions: Mapping::default(),
                 active_subscriptions: Vec::default(),
+                approved_payers: Mapping::default(),
             }
         }
 
@@ -150,151 +345,485 @@ mod subscriptions {
         /// Parameters:
         /// * payment_interval - one of week|month
         /// * intervals_to_pay - number of paid intervales declared by the caller
-        /// * external_channel_handle_id - external identifier, specific for the external channel, used by the notification service
+        /// * external_channel_handles - external identifiers, specific for the external channels, used by the notification service.
+        ///   Notifications are sent to each of them
+        /// * event_kinds - kinds of on-chain events the caller is notified about
         /// Events:
         /// * NewSubscription
         /// Fails:
         /// * when subscription is already registerred
         /// * when invalid payment interval
+        /// * when no channel handles, more than `MAX_CHANNEL_HANDLES`, an empty or a duplicated channel handle provided
+        /// * when no event kinds or a duplicated event kind provided
         /// * when not enough token value transferred to the smart contract call
         #[ink(message, payable)]
         pub fn add_subscription(
             &mut self,
             payment_interval: PaymentInterval,
             intervals_to_pay: u32,
-            external_channel_handle: String,
+            external_channel_handles: Vec<String>,
+            event_kinds: Vec<EventKind>,
         ) -> Result<(), Error> {
             let caller = self.env().caller();
-            // if caller is already subscribed
-            if self.subscriptions.get(caller).is_some() {
-                return Err(Error::AlreadyRegisterred(caller));
-            }
+            self.register_subscription(
+                caller,
+                caller,
+                payment_interval,
+                intervals_to_pay,
+                external_channel_handles,
+                event_kinds,
+            )
+        }
 
-            self.validate_intervals_to_pay(intervals_to_pay)?;
-            self.validate_channel_handle(&external_channel_handle)?;
+        /// Registers new subscrption for a beneficiary account, paid by the caller, e.g. a treasury or a custodian
+        /// paying for notifications about the accounts it manages.
+        /// The beneficiary must approve the caller with `approve_payer` first. The approval is used up by the registration.
+        /// Parameters:
+        /// * beneficiary - account the subscription is registered for. Events published for this account will result in notifications
+        /// * payment_interval - one of week|month
+        /// * intervals_to_pay - number of paid intervales declared by the caller
+        /// * external_channel_handles - external identifiers, specific for the external channels, used by the notification service.
+        ///   Notifications are sent to each of them
+        /// * event_kinds - kinds of on-chain events the beneficiary is notified about
+        /// Events:
+        /// * NewSubscription
+        /// Fails:
+        /// * when the caller has not been approved by the beneficiary
+        /// * when subscription is already registerred for the beneficiary
+        /// * when invalid payment interval
+        /// * when no channel handles, more than `MAX_CHANNEL_HANDLES`, an empty or a duplicated channel handle provided
+        /// * when no event kinds or a duplicated event kind provided
+        /// * when not enough token value transferred to the smart contract call
+        #[ink(message, payable)]
+        pub fn add_subscription_for(
+            &mut self,
+            beneficiary: AccountId,
+            payment_interval: PaymentInterval,
+            intervals_to_pay: u32,
+            external_channel_handles: Vec<String>,
+            event_kinds: Vec<EventKind>,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != beneficiary && self.approved_payers.get(beneficiary) != Some(caller) {
+                return Err(Error::PayerNotApproved(caller));
+            }
 
-            // create new subscription record
-            let curr_block = self.env().block_number();
-            let price_per_interval = self.price_per_interval(&payment_interval);
-            let subscription = Subscription {
+            self.register_subscription(
+                caller,
+                beneficiary,
                 payment_interval,
-                declared_payment_intervals: intervals_to_pay,
-                paid_intervals: 1,
-                price_per_interval,
-                registered_at: curr_block,
-                last_payment_at: curr_block,
-                external_channel_handle: external_channel_handle.clone(),
-            };
+                intervals_to_pay,
+                external_channel_handles,
+                event_kinds,
+            )?;
+            self.approved_payers.remove(beneficiary);
 
-            // Check how many tokens have been transferred as part of the transaction and if are enough to cover current and future payments
-            let transferred_value = self.env().transferred_value();
-            if transferred_value < price_per_interval * intervals_to_pay as u128 {
-                return Err(Error::SubscriptionCostTooHigh(
-                    price_per_interval * intervals_to_pay as u128,
-                ));
+            Ok(())
+        }
+
+        /// Allows the given account to register a subscription on behalf of the caller with `add_subscription_for`.
+        /// Without the approval any account could register a subscription for the caller, blocking the caller's own
+        /// subscription and receiving notifications about the caller's account.
+        /// Parameters:
+        /// * payer - account allowed to pay for the caller's subscription, replacing the previous approval.
+        ///   The approval is revoked if not provided
+        #[ink(message)]
+        pub fn approve_payer(&mut self, payer: Option<AccountId>) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if let Some(payer) = payer {
+                self.approved_payers.insert(caller, &payer);
+            } else {
+                self.approved_payers.remove(caller);
             }
 
-            // Transfer one interval payment to the contract's owner. The tokens needed for the remaining paiments will stay in the contract
-            self.transfer_to_owner(price_per_interval);
+            Ok(())
+        }
 
-            // If user transferred more than expected
-            self.reimburse(
-                caller,
-                transferred_value - price_per_interval * intervals_to_pay as u128,
-            );
+        /// Extends the caller's subscription by a number of payment intervals, keeping the subscription active.
+        /// The transferred token value must cover the price of the added intervals, the surplus is returned to the caller.
+        /// Parameters:
+        /// * intervals - number of payment intervals added to the subscription
+        /// Events:
+        /// * SubscriptionExtended
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the subscription has been paid for by another account, which should extend it with `extend_subscription_for`
+        /// * when invalid number of intervals
+        /// * when not enough token value transferred to the smart contract call
+        #[ink(message, payable)]
+        pub fn extend_subscription(&mut self, intervals: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.prolong_subscription(caller, intervals)
+        }
 
+        /// Extends the subscription of a beneficiary paid for by the caller.
+        /// The transferred token value must cover the price of the added intervals, the surplus is returned to the caller.
+        /// Parameters:
+        /// * beneficiary - account the subscription is registered for
+        /// * intervals - number of payment intervals added to the subscription
+        /// Events:
+        /// * SubscriptionExtended
+        /// Fails:
+        /// * when there is no subscription associated with the beneficiary's account
+        /// * when the subscription has not been paid for by the caller
+        /// * when invalid number of intervals
+        /// * when not enough token value transferred to the smart contract call
+        #[ink(message, payable)]
+        pub fn extend_subscription_for(
+            &mut self,
+            beneficiary: AccountId,
+            intervals: u32,
+        ) -> Result<(), Error> {
+            self.prolong_subscription(beneficiary, intervals)
+        }
+
+        /// Replaces one of the channel handles of the caller's subscription, e.g. after moving to another Telegram chat,
+        /// without cancelling the subscription.
+        /// Parameters:
+        /// * old_handle - channel handle to be replaced
+        /// * new_handle - channel handle notifications are sent to instead
+        /// Events:
+        /// * ChannelHandleUpdated
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the old channel handle is not associated with the subscription
+        /// * when the new channel handle is empty or already associated with the subscription
+        #[ink(message)]
+        pub fn update_channel_handle(
+            &mut self,
+            old_handle: String,
+            new_handle: String,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            let position = subscription
+                .external_channel_handles
+                .iter()
+                .position(|channel_handle| channel_handle == &old_handle)
+                .ok_or_else(|| Error::ChannelHandleNotFound(old_handle.clone()))?;
+
+            subscription.external_channel_handles[position] = new_handle.clone();
+            self.validate_channel_handles(&subscription.external_channel_handles)?;
             self.subscriptions.insert(caller, &subscription);
-            self.active_subscriptions.push(caller);
 
-            self.env().emit_event(NewSubscription {
+            self.env().emit_event(ChannelHandleUpdated {
                 for_account: caller,
-                external_channel_handle: external_channel_handle.into_bytes(),
+                old_handle: old_handle.into_bytes(),
+                new_handle: new_handle.into_bytes(),
             });
 
             Ok(())
         }
 
-        /// Cancels subscription associated with a caller.
-        /// All remaining tokens are transferred back to the caller.
+        /// Replaces the kinds of on-chain events the caller is notified about.
+        /// Parameters:
+        /// * event_kinds - new kinds of on-chain events the caller is notified about
         /// Events:
-        /// * CancelledSubscription
+        /// * EventKindsUpdated
         /// Fails:
-        /// * SubscriptionNotFound - when there is no subscription associated with the caller's account
-        #[ink(message, payable)]
-        pub fn cancel_subscription(&mut self) -> Result<(), Error> {
+        /// * when there is no subscription associated with the caller's account
+        /// * when no event kinds or a duplicated event kind provided
+        #[ink(message)]
+        pub fn update_event_kinds(&mut self, event_kinds: Vec<EventKind>) -> Result<(), Error> {
             let caller = self.env().caller();
 
-            let subscription = self
+            let mut subscription = self
                 .subscriptions
                 .get(caller)
                 .ok_or(Error::NotRegisterred(caller))?;
+            self.validate_event_kinds(&event_kinds)?;
 
-            // Transfer remaining token value
-            let mut to_return: Balance = 0;
-            if subscription.declared_payment_intervals > subscription.paid_intervals {
-                to_return = subscription.price_per_interval
-                    * (subscription.declared_payment_intervals - subscription.paid_intervals)
-                        as u128;
+            subscription.event_kinds = event_kinds.clone();
+            self.subscriptions.insert(caller, &subscription);
+
+            self.env().emit_event(EventKindsUpdated {
+                for_account: caller,
+                event_kinds,
+            });
+
+            Ok(())
+        }
+
+        /// Replaces the minimum amounts of the events the caller is notified about, separately for every event kind,
+        /// e.g. to skip dust transfers. Event kinds without a minimum amount are notified regardless of the amount.
+        /// Parameters:
+        /// * min_amounts - minimum amounts per event kind, in the smallest token unit
+        /// Events:
+        /// * MinAmountsUpdated
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the same event kind provided more than once
+        /// * when the events of the kind carry no amount, i.e. `Committee`, `ContractEvents` and `Dynamic`
+        #[ink(message)]
+        pub fn update_min_amounts(
+            &mut self,
+            min_amounts: Vec<(EventKind, Balance)>,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            for (i, (event_kind, _)) in min_amounts.iter().enumerate() {
+                if min_amounts[..i].iter().any(|(k, _)| k == event_kind) {
+                    return Err(Error::DuplicatedEventKind(*event_kind));
+                }
+                if !event_kind.has_amount() {
+                    return Err(Error::MinAmountNotSupported(*event_kind));
+                }
             }
 
-            // Get all transferred tokens. We need to return them.
-            let transferred_value = self.env().transferred_value();
-            to_return += transferred_value;
+            subscription.min_amounts = min_amounts.clone();
+            self.subscriptions.insert(caller, &subscription);
+
+            self.env().emit_event(MinAmountsUpdated {
+                for_account: caller,
+                min_amounts,
+            });
+
+            Ok(())
+        }
+
+        /// Adds an account watched by the caller's subscription, so that its events are notified as well,
+        /// e.g. a controller or a nominator account of a validator.
+        /// Parameters:
+        /// * account - account to be watched
+        /// Events:
+        /// * WatchedAccountAdded
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the account is the caller's or is already watched
+        /// * when the subscription already watches `MAX_WATCHED_ACCOUNTS` accounts
+        #[ink(message)]
+        pub fn add_watched_account(&mut self, account: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
 
-            // If there is something to return
-            if to_return > 0 {
-                self.reimburse(caller, to_return);
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            if account == caller || subscription.watched_accounts.contains(&account) {
+                return Err(Error::AlreadyWatched(account));
+            }
+            if subscription.watched_accounts.len() >= MAX_WATCHED_ACCOUNTS as usize {
+                return Err(Error::TooManyWatchedAccounts(
+                    subscription.watched_accounts.len() as u32 + 1,
+                ));
             }
 
-            self.subscriptions.remove(caller);
-            self.active_subscriptions.retain(|acct| acct != &caller);
+            subscription.watched_accounts.push(account);
+            self.subscriptions.insert(caller, &subscription);
 
-            self.env().emit_event(CancelledSubscription {
+            self.env().emit_event(WatchedAccountAdded {
                 for_account: caller,
+                account,
             });
 
             Ok(())
         }
 
-        /// Retrieves a list of active subscriptions.
-        /// Returns:
-        /// * list of active subscriptions
-        /// Fails
-        /// * when there is an inconsistent subscription data
+        /// Removes an account watched by the caller's subscription.
+        /// Parameters:
+        /// * account - account no longer watched
+        /// Events:
+        /// * WatchedAccountRemoved
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the account is not watched by the subscription
         #[ink(message)]
-        pub fn get_active_subscriptions(&self) -> Result<Vec<ActiveSubscriptionAttr>, Error> {
-            let mut subs = vec![];
-            for acct_id in &*self.active_subscriptions {
-                let sub = self
-                    .subscriptions
-                    .get(acct_id)
-                    .ok_or(Error::InconsistentSubscriptionData(*acct_id))?;
-                subs.push(ActiveSubscriptionAttr {
-                    for_account: *acct_id,
-                    external_channel_handle: sub.external_channel_handle.into_bytes(),
-                });
+        pub fn remove_watched_account(&mut self, account: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            if !subscription.watched_accounts.contains(&account) {
+                return Err(Error::NotWatched(account));
             }
-            Ok(subs)
+
+            subscription.watched_accounts.retain(|a| a != &account);
+            self.subscriptions.insert(caller, &subscription);
+
+            self.env().emit_event(WatchedAccountRemoved {
+                for_account: caller,
+                account,
+            });
+
+            Ok(())
         }
 
-        /// Run payment settlement for the next subscription round.
-        /// For each active subscription check:
-        /// * is it still active
-        /// * does it have enough funds for the next interval
-        /// If above rules are not fulfilled subscription is automatically cancelled
-        #[ink(message, payable)]
-        pub fn payment_settlement(&mut self) -> Result<(), Error> {
-            self.authorized(self.env().caller())?;
+        /// Pauses notifications of the caller's subscription, e.g. for holidays, without cancelling it.
+        /// Billing freezes while paused: the paused blocks do not count towards payment intervals.
+        /// Events:
+        /// * SubscriptionPaused
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the subscription is already paused
+        #[ink(message)]
+        pub fn pause_subscription(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
 
-            let mut subs_to_cancel: Vec<ActiveSubscriptionAttr> = vec![];
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            if subscription.paused_at.is_some() {
+                return Err(Error::AlreadyPaused(caller));
+            }
 
-            let curr_block = self.env().block_number();
+            subscription.paused_at = Some(self.env().block_number());
+            self.subscriptions.insert(caller, &subscription);
 
-            for acct_id in &*self.active_subscriptions {
-                let mut s = self
-                    .subscriptions
+            self.env().emit_event(SubscriptionPaused {
+                for_account: caller,
+            });
+
+            Ok(())
+        }
+
+        /// Resumes notifications of the caller's paused subscription.
+        /// The next payment is postponed by the number of blocks the subscription was paused for.
+        /// Events:
+        /// * SubscriptionResumed
+        /// Fails:
+        /// * when there is no subscription associated with the caller's account
+        /// * when the subscription is not paused
+        #[ink(message)]
+        pub fn resume_subscription(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+            let paused_at = subscription
+                .paused_at
+                .take()
+                .ok_or(Error::NotPaused(caller))?;
+
+            // shift the last payment, so that the paused blocks are not charged
+            subscription.last_payment_at += self.env().block_number() - paused_at;
+            self.subscriptions.insert(caller, &subscription);
+
+            self.env().emit_event(SubscriptionResumed {
+                for_account: caller,
+            });
+
+            Ok(())
+        }
+
+        /// Cancels subscription associated with a caller.
+        /// All remaining tokens are transferred back to the account which paid for the subscription.
+        /// Events:
+        /// * CancelledSubscription
+        /// Fails:
+        /// * SubscriptionNotFound - when there is no subscription associated with the caller's account
+        #[ink(message, payable)]
+        pub fn cancel_subscription(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let subscription = self
+                .subscriptions
+                .get(caller)
+                .ok_or(Error::NotRegisterred(caller))?;
+
+            self.remove_subscription(caller, subscription.payer, &subscription);
+
+            Ok(())
+        }
+
+        /// Cancels subscription of a beneficiary. Both the beneficiary and the account which paid for the subscription can cancel it.
+        /// Parameters:
+        /// * beneficiary - account the subscription is registered for
+        /// * refund_to - recipient of the remaining tokens, the account which paid for the subscription if not provided.
+        ///   Only the account which paid for the subscription can choose another recipient
+        /// Events:
+        /// * CancelledSubscription
+        /// Fails:
+        /// * when there is no subscription associated with the beneficiary's account
+        /// * when the caller is neither the beneficiary nor the account which paid for the subscription
+        /// * when the beneficiary tries to refund the remaining tokens to an account other than the one which paid for the subscription
+        #[ink(message, payable)]
+        pub fn cancel_subscription_for(
+            &mut self,
+            beneficiary: AccountId,
+            refund_to: Option<AccountId>,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let subscription = self
+                .subscriptions
+                .get(beneficiary)
+                .ok_or(Error::NotRegisterred(beneficiary))?;
+            if caller != beneficiary && caller != subscription.payer {
+                return Err(Error::NotAuthorized);
+            }
+
+            // The remaining tokens belong to the payer, so only the payer can redirect them
+            let refund_to = refund_to.unwrap_or(subscription.payer);
+            if refund_to != subscription.payer && caller != subscription.payer {
+                return Err(Error::NotAuthorized);
+            }
+
+            self.remove_subscription(beneficiary, refund_to, &subscription);
+
+            Ok(())
+        }
+
+        /// Retrieves a list of active subscriptions.
+        /// Returns:
+        /// * list of active subscriptions
+        /// Fails
+        /// * when there is an inconsistent subscription data
+        #[ink(message)]
+        pub fn get_active_subscriptions(&self) -> Result<Vec<ActiveSubscriptionAttr>, Error> {
+            let mut subs = vec![];
+            for acct_id in &*self.active_subscriptions {
+                let sub = self
+                    .subscriptions
+                    .get(acct_id)
+                    .ok_or(Error::InconsistentSubscriptionData(*acct_id))?;
+                subs.push(ActiveSubscriptionAttr {
+                    for_account: *acct_id,
+                    external_channel_handles: Self::handles_to_bytes(sub.external_channel_handles),
+                    event_kinds: sub.event_kinds,
+                    min_amounts: sub.min_amounts,
+                    watched_accounts: sub.watched_accounts,
+                    paused: sub.paused_at.is_some(),
+                });
+            }
+            Ok(subs)
+        }
+
+        /// Run payment settlement for the next subscription round.
+        /// For each active subscription check:
+        /// * is it still active
+        /// * does it have enough funds for the next interval
+        /// Paused subscriptions are skipped
+        /// If above rules are not fulfilled subscription is automatically cancelled
+        #[ink(message, payable)]
+        pub fn payment_settlement(&mut self) -> Result<(), Error> {
+            self.authorized(self.env().caller())?;
+
+            let mut subs_to_cancel: Vec<ActiveSubscriptionAttr> = vec![];
+
+            let curr_block = self.env().block_number();
+
+            for acct_id in &*self.active_subscriptions {
+                let mut s = self
+                    .subscriptions
                     .get(acct_id)
                     .ok_or(Error::InconsistentSubscriptionData(*acct_id))?;
+                // billing is frozen while paused
+                if s.paused_at.is_some() {
+                    continue;
+                }
                 // calculate number of intervals to pay
                 let mut to_pay_intervals =
                     self.to_pay_intervals(s.payment_interval, curr_block, s.last_payment_at);
@@ -322,7 +851,13 @@ mod subscriptions {
                     // add subscription to the list of to be cancelled subsccriptions
                     subs_to_cancel.push(ActiveSubscriptionAttr {
                         for_account: *acct_id,
-                        external_channel_handle: s.external_channel_handle.into_bytes(),
+                        external_channel_handles: Self::handles_to_bytes(
+                            s.external_channel_handles,
+                        ),
+                        event_kinds: s.event_kinds,
+                        min_amounts: s.min_amounts,
+                        watched_accounts: s.watched_accounts,
+                        paused: s.paused_at.is_some(),
                     });
                 } else {
                     self.subscriptions.insert(acct_id, &s);
@@ -368,6 +903,7 @@ mod subscriptions {
         ///
         /// We use this to upgrade the contract logic. We don't do any authorization here, any caller
         /// can execute this method. In a production contract you would do some authorization here.
+        /// The new code must keep the storage layout, stored subscriptions are not migrated.
         #[ink(message)]
         pub fn set_code(&mut self, code_hash: [u8; 32]) -> Result<(), Error> {
             self.authorized(self.env().caller())?;
@@ -381,6 +917,149 @@ mod subscriptions {
             Ok(())
         }
 
+        /// Registers new subscription for the beneficiary, paid by the payer
+        fn register_subscription(
+            &mut self,
+            payer: AccountId,
+            beneficiary: AccountId,
+            payment_interval: PaymentInterval,
+            intervals_to_pay: u32,
+            external_channel_handles: Vec<String>,
+            event_kinds: Vec<EventKind>,
+        ) -> Result<(), Error> {
+            // if beneficiary is already subscribed
+            if self.subscriptions.get(beneficiary).is_some() {
+                return Err(Error::AlreadyRegisterred(beneficiary));
+            }
+
+            self.validate_intervals_to_pay(intervals_to_pay)?;
+            self.validate_channel_handles(&external_channel_handles)?;
+            self.validate_event_kinds(&event_kinds)?;
+
+            // create new subscription record
+            let curr_block = self.env().block_number();
+            let price_per_interval = self.price_per_interval(&payment_interval);
+            let subscription = Subscription {
+                payment_interval,
+                declared_payment_intervals: intervals_to_pay,
+                paid_intervals: 1,
+                price_per_interval,
+                registered_at: curr_block,
+                last_payment_at: curr_block,
+                external_channel_handles: external_channel_handles.clone(),
+                event_kinds: event_kinds.clone(),
+                min_amounts: Vec::new(),
+                watched_accounts: Vec::new(),
+                payer,
+                paused_at: None,
+            };
+
+            // Check how many tokens have been transferred as part of the transaction and if are enough to cover current and future payments
+            let transferred_value = self.env().transferred_value();
+            if transferred_value < price_per_interval * intervals_to_pay as u128 {
+                return Err(Error::SubscriptionCostTooHigh(
+                    price_per_interval * intervals_to_pay as u128,
+                ));
+            }
+
+            // Transfer one interval payment to the contract's owner. The tokens needed for the remaining paiments will stay in the contract
+            self.transfer_to_owner(price_per_interval);
+
+            // If user transferred more than expected
+            self.reimburse(
+                payer,
+                transferred_value - price_per_interval * intervals_to_pay as u128,
+            );
+
+            self.subscriptions.insert(beneficiary, &subscription);
+            self.active_subscriptions.push(beneficiary);
+
+            self.env().emit_event(NewSubscription {
+                for_account: beneficiary,
+                external_channel_handles: Self::handles_to_bytes(external_channel_handles),
+                event_kinds,
+                payer,
+            });
+
+            Ok(())
+        }
+
+        /// Adds paid intervals to the subscription of the given account. Only the account which paid for the subscription
+        /// can extend it, as the unused intervals are refunded to this account on cancellation
+        fn prolong_subscription(
+            &mut self,
+            for_account: AccountId,
+            intervals: u32,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut subscription = self
+                .subscriptions
+                .get(for_account)
+                .ok_or(Error::NotRegisterred(for_account))?;
+            if caller != subscription.payer {
+                return Err(Error::NotAuthorized);
+            }
+            self.validate_intervals_to_pay(intervals)?;
+
+            // Tokens needed for the added intervals stay in the contract until settled
+            let cost = subscription
+                .price_per_interval
+                .checked_mul(intervals as u128)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let declared_payment_intervals = subscription
+                .declared_payment_intervals
+                .checked_add(intervals)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let transferred_value = self.env().transferred_value();
+            if transferred_value < cost {
+                return Err(Error::SubscriptionCostTooHigh(cost));
+            }
+            if transferred_value > cost {
+                self.reimburse(caller, transferred_value - cost);
+            }
+
+            subscription.declared_payment_intervals = declared_payment_intervals;
+            self.subscriptions.insert(for_account, &subscription);
+
+            self.env().emit_event(SubscriptionExtended {
+                for_account,
+                intervals,
+                declared_payment_intervals,
+            });
+
+            Ok(())
+        }
+
+        /// Removes the subscription and returns the remaining tokens to the refund recipient,
+        /// and the tokens transferred to the cancellation call back to the caller
+        fn remove_subscription(
+            &mut self,
+            for_account: AccountId,
+            refund_to: AccountId,
+            subscription: &Subscription,
+        ) {
+            // Transfer remaining token value
+            if subscription.declared_payment_intervals > subscription.paid_intervals {
+                let to_return = subscription.price_per_interval
+                    * (subscription.declared_payment_intervals - subscription.paid_intervals)
+                        as u128;
+                self.reimburse(refund_to, to_return);
+            }
+
+            // Get all transferred tokens. We need to return them.
+            let transferred_value = self.env().transferred_value();
+            if transferred_value > 0 {
+                self.reimburse(self.env().caller(), transferred_value);
+            }
+
+            self.subscriptions.remove(for_account);
+            self.active_subscriptions
+                .retain(|acct| acct != &for_account);
+
+            self.env().emit_event(CancelledSubscription { for_account });
+        }
+
         /// Checks if caller is this smart contract owner
         fn authorized(&self, caller: AccountId) -> Result<(), Error> {
             if caller != self.owner {
@@ -389,14 +1068,46 @@ mod subscriptions {
             Ok(())
         }
 
-        /// Validates channel handle
-        fn validate_channel_handle(&self, channel_handle: &str) -> Result<(), Error> {
-            if channel_handle.is_empty() {
+        /// Validates channel handles
+        fn validate_channel_handles(&self, channel_handles: &[String]) -> Result<(), Error> {
+            if channel_handles.is_empty() {
                 return Err(Error::MissingChannelHandle);
             }
+            if channel_handles.len() > MAX_CHANNEL_HANDLES as usize {
+                return Err(Error::TooManyChannelHandles(channel_handles.len() as u32));
+            }
+            for (i, channel_handle) in channel_handles.iter().enumerate() {
+                if channel_handle.is_empty() {
+                    return Err(Error::MissingChannelHandle);
+                }
+                if channel_handles[..i].contains(channel_handle) {
+                    return Err(Error::DuplicatedChannelHandle(channel_handle.clone()));
+                }
+            }
+            Ok(())
+        }
+
+        /// Validates event kinds
+        fn validate_event_kinds(&self, event_kinds: &[EventKind]) -> Result<(), Error> {
+            if event_kinds.is_empty() {
+                return Err(Error::MissingEventKinds);
+            }
+            for (i, event_kind) in event_kinds.iter().enumerate() {
+                if event_kinds[..i].contains(event_kind) {
+                    return Err(Error::DuplicatedEventKind(*event_kind));
+                }
+            }
             Ok(())
         }
 
+        /// Converts channel handles to the representation exposed externally
+        fn handles_to_bytes(channel_handles: Vec<String>) -> Vec<Vec<u8>> {
+            channel_handles
+                .into_iter()
+                .map(|channel_handle| channel_handle.into_bytes())
+                .collect()
+        }
+
         /// Validates intervals to pay
         fn validate_intervals_to_pay(&self, intervals_to_pay: u32) -> Result<(), Error> {
             if intervals_to_pay == 0 {
@@ -481,7 +1192,12 @@ mod subscriptions {
             ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
             // add subscription
             subscriptions
-                .add_subscription(PaymentInterval::Week, 1, "1111".to_string())
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
                 .unwrap();
             assert!(subscriptions.subscriptions.contains(accounts.charlie));
             assert!(subscriptions
@@ -505,7 +1221,12 @@ mod subscriptions {
 
             // test recorded events
             let events = recorded_events().collect::<Vec<_>>();
-            assert_new_subscription(&events[0], accounts.charlie, "1111".to_string());
+            assert_new_subscription(
+                &events[0],
+                accounts.charlie,
+                vec!["1111".to_string()],
+                vec![EventKind::TransferIn],
+            );
         }
 
         #[ink::test]
@@ -525,7 +1246,12 @@ mod subscriptions {
             ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
             // add subscription
             subscriptions
-                .add_subscription(PaymentInterval::Week, 1, "1111".to_string())
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
                 .unwrap();
             assert!(subscriptions.subscriptions.contains(accounts.charlie));
             assert!(subscriptions
@@ -550,76 +1276,580 @@ mod subscriptions {
             );
             // test recorded events
             let events = recorded_events().collect::<Vec<_>>();
-            assert_new_subscription(&events[0], accounts.charlie, "1111".to_string());
+            assert_new_subscription(
+                &events[0],
+                accounts.charlie,
+                vec!["1111".to_string()],
+                vec![EventKind::TransferIn],
+            );
             assert_cancelled_subscription(&events[1], accounts.charlie);
         }
 
         #[ink::test]
-        fn get_active_subscriptions_works() {
+        fn add_subscription_for_works() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             let mut subscriptions = Subscriptions::new(0u128);
 
-            // prepare balance for the Charlie as the contract caller
-            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
-                accounts.charlie,
-                ONE_TOKEN,
+            // Charlie pays for the subscription of Django, once approved by Django
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                subscriptions.add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                ),
+                Err(Error::PayerNotApproved(accounts.charlie))
             );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
-            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
-            // add subscription
             subscriptions
-                .add_subscription(PaymentInterval::Week, 1, "1111".to_string())
+                .add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                )
                 .unwrap();
-            assert!(subscriptions.subscriptions.contains(accounts.charlie));
-            assert!(subscriptions
-                .active_subscriptions
-                .contains(&accounts.charlie));
+            // the approval is used up by the registration
+            assert!(!subscriptions.approved_payers.contains(accounts.django));
 
-            // test list of active subscriptions
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
             assert_eq!(
-                subscriptions.get_active_subscriptions().unwrap(),
-                vec![ActiveSubscriptionAttr {
-                    for_account: accounts.charlie,
-                    external_channel_handle: "1111".as_bytes().to_vec()
-                }]
+                subscriptions.add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["2222".to_string()],
+                    vec![EventKind::Rewarded],
+                ),
+                Err(Error::AlreadyRegisterred(accounts.django))
+            );
+            assert!(subscriptions.subscriptions.contains(accounts.django));
+            assert!(!subscriptions.subscriptions.contains(accounts.charlie));
+            assert_eq!(
+                subscriptions
+                    .subscriptions
+                    .get(accounts.django)
+                    .unwrap()
+                    .payer,
+                accounts.charlie
             );
+
+            let events = recorded_events().collect::<Vec<_>>();
+            assert_new_subscription(
+                &events[0],
+                accounts.django,
+                vec!["1111".to_string()],
+                vec![EventKind::Rewarded],
+            );
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[0].data[..])
+                .expect("invalid event buffer");
+            if let Event::NewSubscription(NewSubscription { payer, .. }) = decoded_event {
+                assert_eq!(payer, accounts.charlie);
+            } else {
+                panic!("unexpected event kind: expected NewSubcription event")
+            }
         }
 
         #[ink::test]
-        fn payment_settlement_works() {
+        fn add_subscription_for_requires_approval() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
-            let mut subscriptions = Subscriptions::new(1u128);
+            let mut subscriptions = Subscriptions::new(0u128);
 
-            // register subscription for Bob
-            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
-                accounts.bob,
-                ONE_TOKEN,
+            // Eve cannot squat the subscription of Django without Django's approval
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                subscriptions.add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["eve".to_string()],
+                    vec![EventKind::TransferIn],
+                ),
+                Err(Error::PayerNotApproved(accounts.eve))
             );
-            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
-            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
-            subscriptions
-                .add_subscription(PaymentInterval::Week, 2, "1111".to_string())
-                .unwrap();
-            // register subscription for Charlie
-            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
-                accounts.charlie,
-                3 * ONE_TOKEN,
+
+            // approval of Charlie does not let Eve in, and can be revoked
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                subscriptions.add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["eve".to_string()],
+                    vec![EventKind::TransferIn],
+                ),
+                Err(Error::PayerNotApproved(accounts.eve))
             );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(None).unwrap();
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
-            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
-            // add subscription
+            assert_eq!(
+                subscriptions.add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                ),
+                Err(Error::PayerNotApproved(accounts.charlie))
+            );
+
+            // Django can still register its own subscription
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
             subscriptions
-                .add_subscription(PaymentInterval::Week, 3, "2222".to_string())
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["2222".to_string()],
+                    vec![EventKind::TransferIn],
+                )
                 .unwrap();
-
-            assert!(subscriptions.subscriptions.contains(accounts.bob));
-            assert!(subscriptions.active_subscriptions.contains(&accounts.bob));
             assert_eq!(
                 subscriptions
                     .subscriptions
-                    .get(accounts.bob)
+                    .get(accounts.django)
                     .unwrap()
-                    .paid_intervals,
+                    .payer,
+                accounts.django
+            );
+        }
+
+        #[ink::test]
+        fn cancel_subscription_for_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+
+            // Charlie pays for three intervals of the subscription of Django
+            let price_per_interval = subscriptions.price_per_interval(&PaymentInterval::Week);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(3 * price_per_interval);
+            subscriptions
+                .add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    3,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                )
+                .unwrap();
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(0);
+
+            // only the beneficiary and the payer can cancel the subscription
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                subscriptions.cancel_subscription_for(accounts.django, Some(accounts.eve)),
+                Err(Error::NotAuthorized)
+            );
+            assert_eq!(
+                subscriptions.cancel_subscription_for(accounts.eve, None),
+                Err(Error::NotRegisterred(accounts.eve))
+            );
+
+            // the beneficiary cannot redirect the remaining tokens
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                subscriptions.cancel_subscription_for(accounts.django, Some(accounts.django)),
+                Err(Error::NotAuthorized)
+            );
+            assert!(subscriptions.subscriptions.contains(accounts.django));
+
+            // remaining tokens are returned to the given refund recipient
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(accounts.frank, 0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            subscriptions
+                .cancel_subscription_for(accounts.django, Some(accounts.frank))
+                .unwrap();
+            assert!(!subscriptions.subscriptions.contains(accounts.django));
+            assert!(!subscriptions
+                .active_subscriptions
+                .contains(&accounts.django));
+            assert_eq!(
+                2 * price_per_interval,
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.frank)
+                    .unwrap()
+            );
+
+            let events = recorded_events().collect::<Vec<_>>();
+            assert_cancelled_subscription(&events[1], accounts.django);
+        }
+
+        #[ink::test]
+        fn cancel_subscription_for_by_beneficiary_refunds_payer() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+
+            // Charlie pays for three intervals of the subscription of Django
+            let price_per_interval = subscriptions.price_per_interval(&PaymentInterval::Week);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(3 * price_per_interval);
+            subscriptions
+                .add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    3,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                )
+                .unwrap();
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(0);
+
+            // remaining tokens go back to Charlie, not to Django
+            let payer_balance =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                    accounts.charlie,
+                )
+                .unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(accounts.django, 0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions
+                .cancel_subscription_for(accounts.django, None)
+                .unwrap();
+            assert!(!subscriptions.subscriptions.contains(accounts.django));
+            assert_eq!(
+                payer_balance + 2 * price_per_interval,
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                    accounts.charlie
+                )
+                .unwrap()
+            );
+            assert_eq!(
+                0,
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                    accounts.django
+                )
+                .unwrap()
+            );
+        }
+
+        #[ink::test]
+        fn extend_subscription_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+            let price_per_interval = subscriptions.price_per_interval(&PaymentInterval::Week);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can extend subscription
+            assert_eq!(
+                subscriptions.extend_subscription(1),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(price_per_interval);
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.extend_subscription(0),
+                Err(Error::InvalidIntervalsToPay(0))
+            );
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(price_per_interval);
+            assert_eq!(
+                subscriptions.extend_subscription(2),
+                Err(Error::SubscriptionCostTooHigh(2 * price_per_interval))
+            );
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(2 * price_per_interval);
+            subscriptions.extend_subscription(2).unwrap();
+            assert_eq!(
+                subscriptions
+                    .subscriptions
+                    .get(accounts.charlie)
+                    .unwrap()
+                    .declared_payment_intervals,
+                3
+            );
+            assert_eq!(
+                subscriptions.extend_subscription(u32::MAX),
+                Err(Error::ArithmeticOverflow)
+            );
+
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::SubscriptionExtended(SubscriptionExtended {
+                for_account,
+                intervals,
+                declared_payment_intervals,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(intervals, 2);
+                assert_eq!(declared_payment_intervals, 3);
+            } else {
+                panic!("unexpected event kind: expected SubscriptionExtended event")
+            }
+        }
+
+        #[ink::test]
+        fn extend_subscription_for_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+            let price_per_interval = subscriptions.price_per_interval(&PaymentInterval::Week);
+
+            // Charlie pays for the subscription of Django
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            subscriptions.approve_payer(Some(accounts.charlie)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(price_per_interval);
+            subscriptions
+                .add_subscription_for(
+                    accounts.django,
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            // intervals refunded to Charlie on cancellation cannot be paid by Django
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.django,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(price_per_interval);
+            assert_eq!(
+                subscriptions.extend_subscription(1),
+                Err(Error::NotAuthorized)
+            );
+            assert_eq!(
+                subscriptions.extend_subscription_for(accounts.django, 1),
+                Err(Error::NotAuthorized)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(2 * price_per_interval);
+            subscriptions
+                .extend_subscription_for(accounts.django, 2)
+                .unwrap();
+            assert_eq!(
+                subscriptions
+                    .subscriptions
+                    .get(accounts.django)
+                    .unwrap()
+                    .declared_payment_intervals,
+                3
+            );
+        }
+
+        #[ink::test]
+        fn pause_subscription_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can pause subscription
+            assert_eq!(
+                subscriptions.pause_subscription(),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            subscriptions.pause_subscription().unwrap();
+            assert_eq!(
+                subscriptions.pause_subscription(),
+                Err(Error::AlreadyPaused(accounts.charlie))
+            );
+            assert!(subscriptions.get_active_subscriptions().unwrap()[0].paused);
+
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::SubscriptionPaused(SubscriptionPaused { for_account }) = decoded_event {
+                assert_eq!(for_account, accounts.charlie);
+            } else {
+                panic!("unexpected event kind: expected SubscriptionPaused event")
+            }
+        }
+
+        #[ink::test]
+        fn resume_subscription_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+            let price_per_interval = subscriptions.price_per_interval(&PaymentInterval::Week);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(2 * price_per_interval);
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    2,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(
+                subscriptions.resume_subscription(),
+                Err(Error::NotPaused(accounts.charlie))
+            );
+
+            // the paused interval is not charged
+            subscriptions.pause_subscription().unwrap();
+            for _ in 0..BLOCKS_PER_WEEK {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            subscriptions.payment_settlement().unwrap();
+            assert_eq!(
+                subscriptions
+                    .subscriptions
+                    .get(accounts.charlie)
+                    .unwrap()
+                    .paid_intervals,
+                1
+            );
+
+            // the next payment is postponed by the paused blocks
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            subscriptions.resume_subscription().unwrap();
+            let subscription = subscriptions.subscriptions.get(accounts.charlie).unwrap();
+            assert_eq!(subscription.paused_at, None);
+            assert_eq!(
+                subscription.last_payment_at,
+                subscription.registered_at + BLOCKS_PER_WEEK
+            );
+            assert!(!subscriptions.get_active_subscriptions().unwrap()[0].paused);
+
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[2].data[..])
+                .expect("invalid event buffer");
+            if let Event::SubscriptionResumed(SubscriptionResumed { for_account }) = decoded_event {
+                assert_eq!(for_account, accounts.charlie);
+            } else {
+                panic!("unexpected event kind: expected SubscriptionResumed event")
+            }
+        }
+
+        #[ink::test]
+        fn get_active_subscriptions_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            // prepare balance for the Charlie as the contract caller
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
+            // add subscription
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+            assert!(subscriptions.subscriptions.contains(accounts.charlie));
+            assert!(subscriptions
+                .active_subscriptions
+                .contains(&accounts.charlie));
+
+            // test list of active subscriptions
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap(),
+                vec![ActiveSubscriptionAttr {
+                    for_account: accounts.charlie,
+                    external_channel_handles: vec!["1111".as_bytes().to_vec()],
+                    event_kinds: vec![EventKind::TransferIn],
+                    min_amounts: vec![],
+                    watched_accounts: vec![],
+                    paused: false,
+                }]
+            );
+        }
+
+        #[ink::test]
+        fn payment_settlement_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(1u128);
+
+            // register subscription for Bob
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.bob,
+                ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    2,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+            // register subscription for Charlie
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+                3 * ONE_TOKEN,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(ONE_TOKEN);
+            // add subscription
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    3,
+                    vec!["2222".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            assert!(subscriptions.subscriptions.contains(accounts.bob));
+            assert!(subscriptions.active_subscriptions.contains(&accounts.bob));
+            assert_eq!(
+                subscriptions
+                    .subscriptions
+                    .get(accounts.bob)
+                    .unwrap()
+                    .paid_intervals,
                 1
             );
             assert!(subscriptions.subscriptions.contains(accounts.charlie));
@@ -699,17 +1929,418 @@ mod subscriptions {
 
             // test emitted events
             let events = recorded_events().collect::<Vec<_>>();
-            assert_new_subscription(&events[0], accounts.bob, "1111".to_string());
-            assert_new_subscription(&events[1], accounts.charlie, "2222".to_string());
+            assert_new_subscription(
+                &events[0],
+                accounts.bob,
+                vec!["1111".to_string()],
+                vec![EventKind::TransferIn],
+            );
+            assert_new_subscription(
+                &events[1],
+                accounts.charlie,
+                vec!["2222".to_string()],
+                vec![EventKind::TransferIn],
+            );
             assert_cancelled_subscriptions(
                 &events[2],
                 vec![ActiveSubscriptionAttr {
                     for_account: accounts.bob,
-                    external_channel_handle: "1111".as_bytes().to_vec(),
+                    external_channel_handles: vec!["1111".as_bytes().to_vec()],
+                    event_kinds: vec![EventKind::TransferIn],
+                    min_amounts: vec![],
+                    watched_accounts: vec![],
+                    paused: false,
                 }],
             );
         }
 
+        #[ink::test]
+        fn add_subscription_with_multiple_channel_handles_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let channel_handles = vec![
+                "chat_id:1111".to_string(),
+                "webhook:https://example.com/notifications".to_string(),
+            ];
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    channel_handles.clone(),
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            // every channel handle is exposed
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap(),
+                vec![ActiveSubscriptionAttr {
+                    for_account: accounts.charlie,
+                    external_channel_handles: Subscriptions::handles_to_bytes(
+                        channel_handles.clone()
+                    ),
+                    event_kinds: vec![EventKind::TransferIn],
+                    min_amounts: vec![],
+                    watched_accounts: vec![],
+                    paused: false,
+                }]
+            );
+            let events = recorded_events().collect::<Vec<_>>();
+            assert_new_subscription(
+                &events[0],
+                accounts.charlie,
+                channel_handles,
+                vec![EventKind::TransferIn],
+            );
+        }
+
+        #[ink::test]
+        fn add_subscription_with_invalid_channel_handles_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+
+            assert_eq!(
+                subscriptions.add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec![],
+                    vec![EventKind::TransferIn]
+                ),
+                Err(Error::MissingChannelHandle)
+            );
+            assert_eq!(
+                subscriptions.add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string(), "".to_string()],
+                    vec![EventKind::TransferIn]
+                ),
+                Err(Error::MissingChannelHandle)
+            );
+            assert_eq!(
+                subscriptions.add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string(), "1111".to_string()],
+                    vec![EventKind::TransferIn]
+                ),
+                Err(Error::DuplicatedChannelHandle("1111".to_string()))
+            );
+            let too_many = (0..=MAX_CHANNEL_HANDLES)
+                .map(|i| format!("chat_id:{}", i))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                subscriptions.add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    too_many,
+                    vec![EventKind::TransferIn]
+                ),
+                Err(Error::TooManyChannelHandles(MAX_CHANNEL_HANDLES + 1))
+            );
+            assert!(!subscriptions.subscriptions.contains(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn update_channel_handle_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can update channel handles
+            assert_eq!(
+                subscriptions.update_channel_handle("1111".to_string(), "3333".to_string()),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string(), "2222".to_string()],
+                    vec![EventKind::TransferIn],
+                )
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.update_channel_handle("4444".to_string(), "3333".to_string()),
+                Err(Error::ChannelHandleNotFound("4444".to_string()))
+            );
+            assert_eq!(
+                subscriptions.update_channel_handle("1111".to_string(), "".to_string()),
+                Err(Error::MissingChannelHandle)
+            );
+            assert_eq!(
+                subscriptions.update_channel_handle("1111".to_string(), "2222".to_string()),
+                Err(Error::DuplicatedChannelHandle("2222".to_string()))
+            );
+            subscriptions
+                .update_channel_handle("1111".to_string(), "3333".to_string())
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap()[0].external_channel_handles,
+                vec!["3333".as_bytes().to_vec(), "2222".as_bytes().to_vec()]
+            );
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::ChannelHandleUpdated(ChannelHandleUpdated {
+                for_account,
+                old_handle,
+                new_handle,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(old_handle, "1111".as_bytes().to_vec());
+                assert_eq!(new_handle, "3333".as_bytes().to_vec());
+            } else {
+                panic!("unexpected event kind: expected ChannelHandleUpdated event")
+            }
+        }
+
+        #[ink::test]
+        fn update_event_kinds_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can update event kinds
+            assert_eq!(
+                subscriptions.update_event_kinds(vec![EventKind::Rewarded]),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn, EventKind::Rewarded],
+                )
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.update_event_kinds(vec![]),
+                Err(Error::MissingEventKinds)
+            );
+            assert_eq!(
+                subscriptions.update_event_kinds(vec![EventKind::Slashed, EventKind::Slashed]),
+                Err(Error::DuplicatedEventKind(EventKind::Slashed))
+            );
+            subscriptions
+                .update_event_kinds(vec![EventKind::TransferOut, EventKind::Slashed])
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap(),
+                vec![ActiveSubscriptionAttr {
+                    for_account: accounts.charlie,
+                    external_channel_handles: vec!["1111".as_bytes().to_vec()],
+                    event_kinds: vec![EventKind::TransferOut, EventKind::Slashed],
+                    min_amounts: vec![],
+                    watched_accounts: vec![],
+                    paused: false,
+                }]
+            );
+            let events = recorded_events().collect::<Vec<_>>();
+            assert_new_subscription(
+                &events[0],
+                accounts.charlie,
+                vec!["1111".to_string()],
+                vec![EventKind::TransferIn, EventKind::Rewarded],
+            );
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::EventKindsUpdated(EventKindsUpdated {
+                for_account,
+                event_kinds,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(
+                    event_kinds,
+                    vec![EventKind::TransferOut, EventKind::Slashed]
+                );
+            } else {
+                panic!("unexpected event kind: expected EventKindsUpdated event")
+            }
+        }
+
+        #[ink::test]
+        fn update_min_amounts_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can update minimum amounts
+            assert_eq!(
+                subscriptions.update_min_amounts(vec![(EventKind::TransferIn, 100)]),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::TransferIn, EventKind::Rewarded],
+                )
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.update_min_amounts(vec![
+                    (EventKind::TransferIn, 100),
+                    (EventKind::TransferIn, 200)
+                ]),
+                Err(Error::DuplicatedEventKind(EventKind::TransferIn))
+            );
+            // events of some kinds carry no amount
+            assert_eq!(
+                subscriptions.update_min_amounts(vec![
+                    (EventKind::TransferIn, 100),
+                    (EventKind::Dynamic, 1)
+                ]),
+                Err(Error::MinAmountNotSupported(EventKind::Dynamic))
+            );
+            subscriptions
+                .update_min_amounts(vec![(EventKind::TransferIn, 100), (EventKind::Rewarded, 5)])
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap(),
+                vec![ActiveSubscriptionAttr {
+                    for_account: accounts.charlie,
+                    external_channel_handles: vec!["1111".as_bytes().to_vec()],
+                    event_kinds: vec![EventKind::TransferIn, EventKind::Rewarded],
+                    min_amounts: vec![(EventKind::TransferIn, 100), (EventKind::Rewarded, 5)],
+                    watched_accounts: vec![],
+                    paused: false,
+                }]
+            );
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::MinAmountsUpdated(MinAmountsUpdated {
+                for_account,
+                min_amounts,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(
+                    min_amounts,
+                    vec![(EventKind::TransferIn, 100), (EventKind::Rewarded, 5)]
+                );
+            } else {
+                panic!("unexpected event kind: expected MinAmountsUpdated event")
+            }
+        }
+
+        #[ink::test]
+        fn add_watched_account_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            // only registered subscribers can watch accounts
+            assert_eq!(
+                subscriptions.add_watched_account(accounts.django),
+                Err(Error::NotRegisterred(accounts.charlie))
+            );
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                )
+                .unwrap();
+
+            // the subscribed account is always watched
+            assert_eq!(
+                subscriptions.add_watched_account(accounts.charlie),
+                Err(Error::AlreadyWatched(accounts.charlie))
+            );
+            subscriptions.add_watched_account(accounts.django).unwrap();
+            assert_eq!(
+                subscriptions.add_watched_account(accounts.django),
+                Err(Error::AlreadyWatched(accounts.django))
+            );
+            for i in 1..MAX_WATCHED_ACCOUNTS {
+                subscriptions
+                    .add_watched_account(AccountId::from([0x10 + i as u8; 32]))
+                    .unwrap();
+            }
+            assert_eq!(
+                subscriptions.add_watched_account(accounts.eve),
+                Err(Error::TooManyWatchedAccounts(MAX_WATCHED_ACCOUNTS + 1))
+            );
+
+            let active_subscriptions = subscriptions.get_active_subscriptions().unwrap();
+            assert_eq!(
+                active_subscriptions[0].watched_accounts.len(),
+                MAX_WATCHED_ACCOUNTS as usize
+            );
+            assert_eq!(active_subscriptions[0].watched_accounts[0], accounts.django);
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[1].data[..])
+                .expect("invalid event buffer");
+            if let Event::WatchedAccountAdded(WatchedAccountAdded {
+                for_account,
+                account,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(account, accounts.django);
+            } else {
+                panic!("unexpected event kind: expected WatchedAccountAdded event")
+            }
+        }
+
+        #[ink::test]
+        fn remove_watched_account_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut subscriptions = Subscriptions::new(0u128);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            subscriptions
+                .add_subscription(
+                    PaymentInterval::Week,
+                    1,
+                    vec!["1111".to_string()],
+                    vec![EventKind::Rewarded],
+                )
+                .unwrap();
+            subscriptions.add_watched_account(accounts.django).unwrap();
+            subscriptions.add_watched_account(accounts.eve).unwrap();
+
+            assert_eq!(
+                subscriptions.remove_watched_account(accounts.frank),
+                Err(Error::NotWatched(accounts.frank))
+            );
+            subscriptions
+                .remove_watched_account(accounts.django)
+                .unwrap();
+
+            assert_eq!(
+                subscriptions.get_active_subscriptions().unwrap()[0].watched_accounts,
+                vec![accounts.eve]
+            );
+            let events = recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &events[3].data[..])
+                .expect("invalid event buffer");
+            if let Event::WatchedAccountRemoved(WatchedAccountRemoved {
+                for_account,
+                account,
+            }) = decoded_event
+            {
+                assert_eq!(for_account, accounts.charlie);
+                assert_eq!(account, accounts.django);
+            } else {
+                panic!("unexpected event kind: expected WatchedAccountRemoved event")
+            }
+        }
+
         #[ink::test]
         fn only_owner_allowed_to_transfer_ownership() {
             // given
@@ -725,20 +2356,24 @@ mod subscriptions {
         fn assert_new_subscription(
             event: &EmittedEvent,
             expected_for_account: AccountId,
-            expected_external_channel_handle: String,
+            expected_external_channel_handles: Vec<String>,
+            expected_event_kinds: Vec<EventKind>,
         ) {
             let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
                 .expect("invalid event buffer");
             if let Event::NewSubscription(NewSubscription {
                 for_account,
-                external_channel_handle,
+                external_channel_handles,
+                event_kinds,
+                ..
             }) = decoded_event
             {
                 assert_eq!(for_account, expected_for_account);
                 assert_eq!(
-                    external_channel_handle,
-                    expected_external_channel_handle.into_bytes()
+                    external_channel_handles,
+                    Subscriptions::handles_to_bytes(expected_external_channel_handles)
                 );
+                assert_eq!(event_kinds, expected_event_kinds);
             } else {
                 panic!("unexpected event kind: expected NewSubcription event")
             }
@@ -0,0 +1,267 @@
+use std::{
+    pin::Pin,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use aleph_client::{AlephConfig, BlockNumber};
+use anyhow::{anyhow, Context, Result};
+use futures::{Stream, StreamExt};
+use subxt::{blocks::Block, Metadata, OnlineClient};
+
+use crate::store::Cursor;
+
+/// Finalized block delivered by the aleph zero node
+pub type AlephBlock = Block<AlephConfig, OnlineClient<AlephConfig>>;
+
+type BlockStream = Pin<Box<dyn Stream<Item = Result<AlephBlock, subxt::Error>> + Send>>;
+
+/// Delay before the first reconnection attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper limit of the delay between reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Number of failed attempts to process a block after which the block is skipped
+const MAX_BLOCK_ATTEMPTS: u32 = 5;
+
+/// Stream of finalized blocks resumed from the last processed block.
+/// Blocks finalized after the cursor and before the first live block are replayed first.
+/// When the live stream fails or ends, it reconnects with exponential backoff, rotating through the node addresses,
+/// and continues right after the last delivered block.
+pub struct FinalizedBlocks {
+    /// Terminates reconnection attempts
+    term: Arc<AtomicBool>,
+
+    /// Webservice endpoint addresses of the Aleph Zero nodes, used in turn on reconnection
+    node_addresses: Vec<String>,
+
+    /// Index of the currently connected node address
+    node_idx: usize,
+
+    /// Client connected to the current node
+    client: OnlineClient<AlephConfig>,
+
+    /// Live stream of finalized blocks. Not set until subscribed
+    live: Option<BlockStream>,
+
+    /// Number of the next block to deliver. Unknown if there is no cursor yet
+    next_block: Option<BlockNumber>,
+
+    /// Live block awaiting delivery until all the missed blocks are replayed
+    pending: Option<AlephBlock>,
+
+    /// Delay before the next reconnection attempt. Not set while the connection is healthy
+    backoff: Option<Duration>,
+
+    /// Number of the block failed to be processed and the number of its failed attempts
+    failures: Option<(BlockNumber, u32)>,
+}
+
+impl FinalizedBlocks {
+    /// Creates the stream of finalized blocks, starting right after the given cursor.
+    /// The client must be connected to the first of the node addresses
+    pub fn new(
+        term: Arc<AtomicBool>,
+        client: OnlineClient<AlephConfig>,
+        node_addresses: Vec<String>,
+        cursor: Option<Cursor>,
+    ) -> Self {
+        if let Some(cursor) = cursor {
+            log::info!(
+                "Resuming from block: {} ({:?})",
+                cursor.block_number,
+                cursor.block_hash
+            );
+        }
+
+        Self {
+            term,
+            node_addresses,
+            node_idx: 0,
+            client,
+            live: None,
+            next_block: cursor.map(|c| c.block_number + 1),
+            pending: None,
+            backoff: None,
+            failures: None,
+        }
+    }
+
+    /// Returns the next finalized block in order. Missed blocks are replayed before live ones.
+    /// Returns `None` only when terminated
+    pub async fn next(&mut self) -> Option<AlephBlock> {
+        loop {
+            if self.term.load(std::sync::atomic::Ordering::Relaxed) {
+                return None;
+            }
+
+            if let Some(backoff) = self.backoff {
+                log::info!("Reconnecting in {:?}...", backoff);
+                tokio::time::sleep(backoff).await;
+                self.backoff = Some((backoff * 2).min(MAX_BACKOFF));
+                if let Err(err) = self.reconnect().await {
+                    log::error!("Reconnection failed: {:?}", err);
+                    continue;
+                }
+            }
+
+            match self.try_next().await {
+                Ok(block) => return Some(block),
+                Err(err) => {
+                    log::error!("Finalized block stream failed: {:?}", err);
+                    self.live = None;
+                    self.pending = None;
+                    self.backoff = self.backoff.or(Some(INITIAL_BACKOFF));
+                }
+            }
+        }
+    }
+
+    /// Returns the runtime metadata of the connected node, used to decode events dynamically
+    pub fn metadata(&self) -> Metadata {
+        self.client.metadata()
+    }
+
+    /// Marks the block as not processed. It is delivered again after reconnection, unless it failed too many times.
+    /// Returns `false` if the block is skipped
+    pub fn replay(&mut self, block: &AlephBlock) -> bool {
+        if self.skip_failed(block.number()) {
+            return false;
+        }
+        self.next_block = Some(block.number());
+        self.live = None;
+        self.pending = None;
+        self.backoff = self.backoff.or(Some(INITIAL_BACKOFF));
+        true
+    }
+
+    /// Counts the failed attempt to process the block. Returns `true` if the block should be skipped.
+    /// Blocks older than the pruning window of the node, e.g. replayed after a long downtime,
+    /// can't be processed by a node without the archive of the state
+    fn skip_failed(&mut self, number: BlockNumber) -> bool {
+        let attempts = match self.failures {
+            Some((failed, attempts)) if failed == number => attempts + 1,
+            _ => 1,
+        };
+        if attempts < MAX_BLOCK_ATTEMPTS {
+            self.failures = Some((number, attempts));
+            return false;
+        }
+
+        log::error!(
+            "Skipping block {} after {} failed attempts. If its state is pruned, an archive node is required to replay it",
+            number,
+            attempts
+        );
+        self.failures = None;
+        true
+    }
+
+    async fn try_next(&mut self) -> Result<AlephBlock> {
+        loop {
+            let pending_number = match &self.pending {
+                Some(block) => block.number(),
+                None => {
+                    let block = self
+                        .live_stream()
+                        .await?
+                        .next()
+                        .await
+                        .ok_or_else(|| anyhow!("No more blocks to proceed"))??;
+                    // the connection is healthy again
+                    self.backoff = None;
+                    let number = block.number();
+                    self.pending = Some(block);
+                    number
+                }
+            };
+
+            match self.next_block {
+                // replay a block finalized while the service was not listening
+                Some(number) if number < pending_number => {
+                    let block = match self.block_at(number).await {
+                        Ok(block) => block,
+                        Err(err) if self.skip_failed(number) => {
+                            log::error!("{:?}", err);
+                            self.next_block = Some(number + 1);
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    };
+                    self.next_block = Some(number + 1);
+                    return Ok(block);
+                }
+                // the live block has already been processed
+                Some(number) if number > pending_number => {
+                    self.pending = None;
+                }
+                _ => {
+                    self.next_block = Some(pending_number + 1);
+                    return self
+                        .pending
+                        .take()
+                        .ok_or_else(|| anyhow!("Missing pending block"));
+                }
+            }
+        }
+    }
+
+    /// Returns the live stream of finalized blocks, subscribing to it if needed
+    async fn live_stream(&mut self) -> Result<&mut BlockStream> {
+        if self.live.is_none() {
+            let live = self
+                .client
+                .blocks()
+                .subscribe_finalized()
+                .await
+                .context("Failed to subscribe to the finalized block stream")?;
+            self.live = Some(live);
+        }
+        self.live
+            .as_mut()
+            .ok_or_else(|| anyhow!("Missing finalized block stream"))
+    }
+
+    /// Connects to the next node address from the list
+    async fn reconnect(&mut self) -> Result<()> {
+        self.node_idx = (self.node_idx + 1) % self.node_addresses.len();
+        let node_address = &self.node_addresses[self.node_idx];
+
+        log::info!("Connecting to node: {}", node_address);
+        self.client = OnlineClient::<AlephConfig>::from_url(node_address)
+            .await
+            .with_context(|| format!("Failed to connect to node: {}", node_address))?;
+        log::info!("Connected to node: {}", node_address);
+
+        Ok(())
+    }
+
+    /// Fetches the finalized block with a given number
+    async fn block_at(&self, number: BlockNumber) -> Result<AlephBlock> {
+        log::debug!("Replaying block: {}", number);
+
+        let hash = self
+            .client
+            .rpc()
+            .block_hash(Some(number.into()))
+            .await
+            .with_context(|| format!("Failed to get hash of the block: {}", number))?
+            .ok_or_else(|| anyhow!("Block not found: {}", number))?;
+
+        self.client
+            .blocks()
+            .at(hash)
+            .await
+            .with_context(|| format!("Failed to get the block: {}", number))
+    }
+}
+
+impl From<&AlephBlock> for Cursor {
+    fn from(block: &AlephBlock) -> Self {
+        Cursor {
+            block_number: block.number(),
+            block_hash: block.hash(),
+        }
+    }
+}
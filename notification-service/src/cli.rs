@@ -1,7 +1,12 @@
 use std::path::PathBuf;
 
 use aleph_client::AccountId;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    contract_events::WatchedContract, dynamic_events::DynamicEvent, events::EventKind,
+    notifications::email::SmtpSecurity,
+};
 
 /// Utilities to interact with Aleph Zero events
 #[derive(Parser, Debug)]
@@ -15,9 +20,15 @@ pub struct Cli {
     )]
     pub log_level: String,
 
-    /// Webservice endpoint address of the Aleph Zero node
-    #[clap(short = 'n', long = "node", default_value = "ws://localhost:9944")]
-    pub node_address: String,
+    /// Comma separated webservice endpoint addresses of the Aleph Zero nodes.
+    /// The first one is used on startup, the others are fallbacks rotated through on reconnection
+    #[clap(
+        short = 'n',
+        long = "node",
+        default_value = "ws://localhost:9944",
+        value_delimiter = ','
+    )]
+    pub node_addresses: Vec<String>,
 
     /// On chain address of KYB registry smart contract
     #[clap(short = 'c')]
@@ -27,6 +38,34 @@ pub struct Cli {
     #[clap(short = 'm', default_value = "metadata.json", value_parser = parsing::parse_path)]
     pub sc_metadata: PathBuf,
 
+    /// Path to the local store keeping the last processed block, used to resume after restart
+    #[clap(
+        short = 's',
+        long,
+        default_value = "notification_service.db",
+        value_parser = parsing::parse_path
+    )]
+    pub store: PathBuf,
+
+    /// Contract whose events are watched for subscribers, given as `<address>=<metadata path>`,
+    /// e.g. a PSP22 token or a DEX pool. May be repeated
+    #[clap(long = "watch-contract", value_name = "ADDRESS=METADATA")]
+    pub watched_contracts: Vec<WatchedContract>,
+
+    /// PSP22 token contract whose `Transfer` and `Approval` events are watched for subscribers,
+    /// given as `<address>=<metadata path>`. May be repeated
+    #[clap(long = "watch-psp22", value_name = "ADDRESS=METADATA")]
+    pub watched_tokens: Vec<WatchedContract>,
+
+    /// Event decoded with the dynamic runtime metadata, given as `<Pallet>.<Event>=<account field>[,<account field>...]`,
+    /// e.g. `Staking.Chilled=stash`. The account fields are matched against subscriptions. May be repeated
+    #[clap(long = "dynamic-event", value_name = "PALLET.EVENT=FIELDS")]
+    pub dynamic_events: Vec<DynamicEvent>,
+
+    /// Number of delivery attempts after which notification is moved to dead letters
+    #[clap(long, default_value = "10")]
+    pub max_delivery_attempts: u32,
+
     /// Commands to interact with Aleph Zero events
     #[clap(subcommand)]
     pub commands: Commands,
@@ -35,32 +74,166 @@ pub struct Cli {
 /// Commands to capture blockchain events
 #[derive(Clone, Eq, PartialEq, Debug, Subcommand)]
 pub enum Commands {
-    /// Capture finalized transfer events for a given on-chain account
+    /// Capture finalized incoming and outgoing transfer events for a given on-chain account
     TransferEvent {
-        /// Commands defining the target notification channel
-        #[clap(subcommand)]
-        targets: Targets,
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
     },
 
     /// Capture finalized validator rewarded event for a given on-chain account
     RewardedEvent {
-        /// Commands definig the target notification channel
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized slashed and slash reported events for a given on-chain account
+    SlashedEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized bonded, unbonded and withdrawn events for a given on-chain account
+    BondingEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized nomination pool events for a given pool member account
+    PoolEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized validator ban and validators change events for a given validator account
+    CommitteeEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized events of the watched contracts for a given on-chain account
+    ContractEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized transfer and approval events of the watched PSP22 tokens for a given on-chain account
+    Psp22Event {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture finalized events listed with `--dynamic-event` for a given on-chain account
+    DynamicEvent {
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Capture all supported finalized events from a single block stream
+    AllEvents {
+        /// Comma separated list of captured event kinds. All supported kinds if not set
+        #[arg(short = 'k', long, value_delimiter = ',')]
+        kinds: Vec<EventKind>,
+
+        /// Notification channels the notifications are sent to
+        #[command(flatten)]
+        channels: Channels,
+    },
+
+    /// Inspect and re-drive notifications that could not be delivered
+    DeadLetters {
+        /// Commands to manage dead letters
         #[clap(subcommand)]
-        targets: Targets,
+        commands: DeadLetterCommands,
     },
 }
 
-/// Commands to define target channel for notifications
+/// Commands to manage notifications that could not be delivered
 #[derive(Debug, Clone, Eq, PartialEq, Subcommand)]
-pub enum Targets {
-    /// Notification are sent to the Telegram bot
-    Telegram {
-        /// Telegram bot token
-        #[arg(short = 't', long)]
-        token: String,
+pub enum DeadLetterCommands {
+    /// List dead letters stored in the local store
+    List,
+
+    /// Move dead letters back to the outbox for immediate delivery
+    Redrive {
+        /// Identifier of the dead letter to re-drive. All dead letters if not set
+        #[arg(long)]
+        id: Option<i64>,
     },
 }
 
+/// Options configuring notification channels. Every configured channel is used at once,
+/// chosen for every subscription by the scheme prefix of its channel handle
+#[derive(Debug, Clone, Eq, PartialEq, Args)]
+pub struct Channels {
+    /// Telegram bot token. Enables `telegram:` channel handles and Telegram handles without the prefix
+    #[arg(short = 't', long)]
+    pub telegram_token: Option<String>,
+
+    /// Secret used to sign webhook request body with HMAC-SHA256. Enables `webhook:<url>` channel handles
+    #[arg(long)]
+    pub webhook_secret: Option<String>,
+
+    /// Host name of the SMTP server. Enables `email:<address>` channel handles
+    #[arg(long, requires = "email_from")]
+    pub smtp_host: Option<String>,
+
+    /// Port of the SMTP server
+    #[arg(long, default_value = "587")]
+    pub smtp_port: u16,
+
+    /// Security of the connection to the SMTP server
+    #[arg(long, value_enum, default_value = "starttls")]
+    pub smtp_security: SmtpSecurity,
+
+    /// User name used to authenticate to the SMTP server
+    #[arg(long, requires = "smtp_password")]
+    pub smtp_username: Option<String>,
+
+    /// Password used to authenticate to the SMTP server
+    #[arg(long, requires = "smtp_username")]
+    pub smtp_password: Option<String>,
+
+    /// Sender address of the emails, e.g. `Notifications <notifications@example.com>`
+    #[arg(long, requires = "smtp_host")]
+    pub email_from: Option<String>,
+
+    /// Enables `discord:<url>` channel handles of Discord incoming webhooks
+    #[arg(long)]
+    pub discord: bool,
+
+    /// Enables `slack:<url>` channel handles of Slack incoming webhooks
+    #[arg(long)]
+    pub slack: bool,
+
+    /// Access token of the Matrix user sending the messages. Enables `matrix:<room id>` channel handles
+    #[arg(long)]
+    pub matrix_access_token: Option<String>,
+
+    /// Base URL of the Matrix homeserver client-server API
+    #[arg(long, default_value = "https://matrix.org")]
+    pub matrix_homeserver_url: String,
+
+    /// Base URL of the block explorer, used to link the block of the event
+    #[arg(long, default_value = DEFAULT_EXPLORER_URL)]
+    pub explorer_url: String,
+
+    /// Timeout of the requests to the notification channels in seconds
+    #[arg(long, default_value = "10")]
+    pub timeout: u64,
+}
+
+/// Block explorer linked from the notifications
+const DEFAULT_EXPLORER_URL: &str = "https://alephzero.subscan.io/block";
+
 mod parsing {
     use std::{path::PathBuf, str::FromStr};
 
@@ -0,0 +1,182 @@
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use aleph_client::{
+    contract::{event::translate_events, ContractInstance, ConvertibleValue},
+    AccountId, AlephConfig,
+};
+use anyhow::{anyhow, Context, Result};
+use contract_transcode::Value;
+use subxt::events::EventDetails;
+
+use crate::notifications::ContractEventNotification;
+
+/// Contract registered by the operator to have its events watched, given as `<address>=<metadata path>`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WatchedContract {
+    /// On chain address of the contract
+    pub address: AccountId,
+    /// Path to the contract's metadata json file
+    pub metadata: PathBuf,
+}
+
+impl FromStr for WatchedContract {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (address, metadata) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected `<address>=<metadata path>` but got: {:?}", s))?;
+        let address = AccountId::from_str(address)
+            .map_err(|e| anyhow!("invalid contract address {:?}: {}", address, e))?;
+        let metadata = shellexpand::full(metadata).context("failed to expand the path")?;
+
+        Ok(Self {
+            address,
+            metadata: PathBuf::from(metadata.as_ref()),
+        })
+    }
+}
+
+/// Decodes events of the contracts registered by the operator, e.g. PSP22 tokens or DEX pools
+pub struct ContractEvents {
+    /// Clients of the watched contracts, built from their metadata
+    contracts: Vec<ContractInstance>,
+}
+
+impl std::fmt::Debug for ContractEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.contracts.iter().map(|c| c.address()))
+            .finish()
+    }
+}
+
+impl ContractEvents {
+    /// Loads metadata of every watched contract
+    pub fn new(watched_contracts: &[WatchedContract]) -> Result<Self> {
+        let contracts = watched_contracts
+            .iter()
+            .map(|contract| {
+                let metadata_path = contract
+                    .metadata
+                    .to_str()
+                    .context("Contract's metadata path is not valid unicode")?;
+                ContractInstance::new(contract.address.clone(), metadata_path).with_context(|| {
+                    format!(
+                        "Failed to load metadata of the contract {}",
+                        contract.address
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { contracts })
+    }
+
+    /// Returns true if no contract is watched
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+    }
+
+    /// Decodes the event if it was emitted by one of the watched contracts, and returns notifications about it
+    /// for every account found in the event fields, e.g. `from` and `to` of a token transfer.
+    /// Decoding failures are logged and skipped
+    pub fn notifications(
+        &self,
+        event: &EventDetails<AlephConfig>,
+    ) -> Vec<(AccountId, ContractEventNotification)> {
+        let contracts = self.contracts.iter().collect::<Vec<_>>();
+        let mut notifications = vec![];
+
+        for contract_event in translate_events(
+            std::iter::once(Ok::<_, subxt::Error>(event.clone())),
+            &contracts,
+            None,
+        ) {
+            let contract_event = match contract_event {
+                Ok(contract_event) => contract_event,
+                Err(err) => {
+                    log::warn!("Failed to decode watched contract event: {:?}", err);
+                    continue;
+                }
+            };
+
+            let fields = contract_event
+                .data
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_string()))
+                .collect::<BTreeMap<_, _>>();
+            let mut accounts = vec![];
+            for value in contract_event.data.values() {
+                collect_accounts(value, &mut accounts);
+            }
+            accounts.sort();
+            accounts.dedup();
+
+            for account in accounts {
+                notifications.push((
+                    account.clone(),
+                    ContractEventNotification {
+                        contract: contract_event.contract.clone(),
+                        event_name: contract_event.name.clone().unwrap_or_default(),
+                        account,
+                        fields: fields.clone(),
+                    },
+                ));
+            }
+        }
+        notifications
+    }
+}
+
+/// Collects accounts found in the decoded value, including nested ones, e.g. `Option<AccountId>`
+fn collect_accounts(value: &Value, accounts: &mut Vec<AccountId>) {
+    if let Ok(account) = AccountId::try_from(ConvertibleValue(value.clone())) {
+        accounts.push(account);
+        return;
+    }
+    match value {
+        Value::Map(map) => map.values().for_each(|v| collect_accounts(v, accounts)),
+        Value::Tuple(tuple) => tuple.values().for_each(|v| collect_accounts(v, accounts)),
+        Value::Seq(seq) => seq
+            .elems()
+            .iter()
+            .for_each(|v| collect_accounts(v, accounts)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn parses_watched_contract() {
+        let watched =
+            WatchedContract::from_str(&format!("{}=metadata/pool.json", ADDRESS)).unwrap();
+
+        assert_eq!(watched.address, AccountId::from_str(ADDRESS).unwrap());
+        assert_eq!(watched.metadata, PathBuf::from("metadata/pool.json"));
+    }
+
+    #[test]
+    fn splits_at_the_first_equals_sign() {
+        let watched = WatchedContract::from_str(&format!("{}=dir=1/pool.json", ADDRESS)).unwrap();
+
+        assert_eq!(watched.metadata, PathBuf::from("dir=1/pool.json"));
+    }
+
+    #[test]
+    fn rejects_invalid_watched_contract() {
+        for arg in [
+            ADDRESS.to_string(),
+            "metadata.json".to_string(),
+            "not-an-address=metadata.json".to_string(),
+            format!("{}x=metadata.json", ADDRESS),
+        ] {
+            assert!(WatchedContract::from_str(&arg).is_err(), "{}", arg);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use aleph_client::{AccountId, AlephConfig};
+use anyhow::{anyhow, bail, Result};
+use subxt::{
+    events::EventDetails,
+    ext::scale_value::{Composite, Primitive, Value, ValueDef},
+    Metadata,
+};
+
+use crate::notifications::DynamicEventNotification;
+
+/// Event decoded through the runtime metadata instead of a compile-time type,
+/// given as `<Pallet>.<Event>=<account field>[,<account field>...]`, e.g. `Staking.Chilled=stash`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicEvent {
+    /// Name of the pallet emitting the event, e.g. `Staking`
+    pub pallet: String,
+    /// Name of the event, e.g. `Chilled`
+    pub variant: String,
+    /// Names of the event fields holding accounts matched against subscriptions
+    pub account_fields: Vec<String>,
+}
+
+impl FromStr for DynamicEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, fields) = s.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "expected `<Pallet>.<Event>=<account field>[,<account field>...]` but got: {:?}",
+                s
+            )
+        })?;
+        let (pallet, variant) = name
+            .split_once('.')
+            .ok_or_else(|| anyhow!("expected `<Pallet>.<Event>` but got: {:?}", name))?;
+        let account_fields = fields
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<_>>();
+        if pallet.is_empty() || variant.is_empty() || account_fields.is_empty() {
+            bail!("missing pallet, event or account field names in: {:?}", s);
+        }
+
+        Ok(Self {
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            account_fields,
+        })
+    }
+}
+
+/// Decodes the events listed by the operator with the dynamic runtime metadata,
+/// so new events are supported after a runtime upgrade without a rebuild
+#[derive(Debug)]
+pub struct DynamicEvents {
+    events: Vec<DynamicEvent>,
+}
+
+impl DynamicEvents {
+    /// Creates the decoder of the given events
+    pub fn new(events: Vec<DynamicEvent>) -> Self {
+        Self { events }
+    }
+
+    /// Returns true if no event is listed
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Decodes the event if it is listed, and returns notifications about it for every account
+    /// found in its account fields. Decoding failures are logged and skipped
+    pub fn notifications(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        metadata: &Metadata,
+    ) -> Vec<(AccountId, DynamicEventNotification)> {
+        let dynamic_event = match self
+            .events
+            .iter()
+            .find(|e| e.pallet == event.pallet_name() && e.variant == event.variant_name())
+        {
+            Some(dynamic_event) => dynamic_event,
+            None => return vec![],
+        };
+        let field_values = match event.field_values() {
+            Ok(field_values) => field_values,
+            Err(err) => {
+                log::warn!(
+                    "Failed to decode {}.{} event: {}",
+                    dynamic_event.pallet,
+                    dynamic_event.variant,
+                    err
+                );
+                return vec![];
+            }
+        };
+        let named_fields = match &field_values {
+            Composite::Named(fields) => fields,
+            Composite::Unnamed(_) => {
+                log::warn!(
+                    "{}.{} event has no named fields",
+                    dynamic_event.pallet,
+                    dynamic_event.variant
+                );
+                return vec![];
+            }
+        };
+
+        let fields = named_fields
+            .iter()
+            .map(|(name, value)| {
+                let value = match as_account(value, metadata) {
+                    Some(account) => account.to_string(),
+                    None => value.to_string(),
+                };
+                (name.clone(), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut accounts = vec![];
+        for (name, value) in named_fields {
+            if dynamic_event.account_fields.contains(name) {
+                collect_accounts(value, metadata, &mut accounts);
+            }
+        }
+        accounts.sort();
+        accounts.dedup();
+
+        accounts
+            .into_iter()
+            .map(|account| {
+                (
+                    account.clone(),
+                    DynamicEventNotification {
+                        pallet: dynamic_event.pallet.clone(),
+                        event_name: dynamic_event.variant.clone(),
+                        account,
+                        fields: fields.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Collects accounts found in the decoded value, including nested ones, e.g. `Option<AccountId>` or `Vec<AccountId>`
+fn collect_accounts(value: &Value<u32>, metadata: &Metadata, accounts: &mut Vec<AccountId>) {
+    if let Some(account) = as_account(value, metadata) {
+        accounts.push(account);
+        return;
+    }
+    match &value.value {
+        ValueDef::Composite(composite) => composite
+            .values()
+            .for_each(|v| collect_accounts(v, metadata, accounts)),
+        ValueDef::Variant(variant) => variant
+            .values
+            .values()
+            .for_each(|v| collect_accounts(v, metadata, accounts)),
+        _ => (),
+    }
+}
+
+/// Name of the metadata type of the accounts
+const ACCOUNT_TYPE_NAME: &str = "AccountId32";
+
+/// Decodes the value as an account if its metadata type is `AccountId32`,
+/// so other 32 byte values, e.g. hashes, are not taken for accounts
+fn as_account(value: &Value<u32>, metadata: &Metadata) -> Option<AccountId> {
+    let is_account = metadata
+        .types()
+        .resolve(value.context)
+        .and_then(|ty| ty.path.segments.last())
+        .is_some_and(|name| name == ACCOUNT_TYPE_NAME);
+    if !is_account {
+        return None;
+    }
+    account_bytes(value)
+}
+
+/// Decodes the value as 32 bytes, possibly wrapped in single field composites
+fn account_bytes<T>(value: &Value<T>) -> Option<AccountId> {
+    let composite = match &value.value {
+        ValueDef::Composite(composite) => composite,
+        _ => return None,
+    };
+    let values = composite.values().collect::<Vec<_>>();
+    if values.len() == 1 {
+        return account_bytes(values[0]);
+    }
+
+    let bytes = values
+        .into_iter()
+        .map(|v| match v.value {
+            ValueDef::Primitive(Primitive::U128(b)) => u8::try_from(b).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    <[u8; 32]>::try_from(bytes).ok().map(AccountId::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dynamic_event() {
+        let event = DynamicEvent::from_str("Staking.Chilled=stash").unwrap();
+
+        assert_eq!(
+            event,
+            DynamicEvent {
+                pallet: "Staking".to_string(),
+                variant: "Chilled".to_string(),
+                account_fields: vec!["stash".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_several_account_fields() {
+        let event = DynamicEvent::from_str("Balances.Transfer=from, to,").unwrap();
+
+        assert_eq!(event.pallet, "Balances");
+        assert_eq!(event.variant, "Transfer");
+        assert_eq!(event.account_fields, vec!["from", "to"]);
+    }
+
+    #[test]
+    fn rejects_invalid_dynamic_event() {
+        for s in [
+            "Staking.Chilled",
+            "StakingChilled=stash",
+            ".Chilled=stash",
+            "Staking.=stash",
+            "Staking.Chilled=",
+            "Staking.Chilled= , ",
+        ] {
+            assert!(DynamicEvent::from_str(s).is_err(), "{:?} was parsed", s);
+        }
+    }
+}
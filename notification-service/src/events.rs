@@ -1,21 +1,96 @@
 use std::{
-    collections::HashMap,
+    collections::HashSet,
+    future::Future,
     sync::{atomic::AtomicBool, Arc, Mutex},
 };
 
 use aleph_client::{
-    api::{balances::events::Transfer, staking::events::Rewarded},
-    AccountId, Connection,
+    api::{
+        balances::events::Transfer,
+        committee_management::events::BanValidators,
+        elections::events::ChangeValidators,
+        nomination_pools::events as pool_events,
+        staking::events::{Bonded, Rewarded, SlashReported, Slashed, Unbonded, Withdrawn},
+    },
+    AccountId, AlephConfig, Balance, Connection,
 };
 use anyhow::{bail, Context, Result};
-use futures::StreamExt;
-use subxt::events::StaticEvent;
+use subxt::{
+    events::{EventDetails, StaticEvent},
+    utils::Static,
+    Metadata,
+};
 
 use crate::{
-    notifications::{ChannelHandle, NotificationMessage, NotificationSender},
-    subscriptions::Subscription,
+    blocks::{AlephBlock, FinalizedBlocks},
+    contract_events::ContractEvents,
+    dynamic_events::DynamicEvents,
+    notifications::{
+        ChannelHandle, EventBlock, EventNotification, Notification, TransferDirection,
+        ValidatorBannedNotification, ValidatorsChangedNotification,
+    },
+    outbox::Outbox,
+    psp22::Psp22Tokens,
+    store::{Cursor, Store, EVENTS_CURSOR},
+    subscriptions::ActiveSubscriptions,
 };
 
+/// Kinds of on-chain events that can be turned into notifications
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, clap::ValueEnum)]
+pub enum EventKind {
+    /// Balances pallet `Transfer` event to the subscribed account
+    TransferIn,
+    /// Balances pallet `Transfer` event from the subscribed account
+    TransferOut,
+    /// Staking pallet `Rewarded` event
+    Rewarded,
+    /// Staking pallet `Slashed` and `SlashReported` events
+    Slashed,
+    /// Staking pallet `Bonded` event
+    Bonded,
+    /// Staking pallet `Unbonded` event
+    Unbonded,
+    /// Staking pallet `Withdrawn` event
+    Withdrawn,
+    /// Nomination pools pallet `PaidOut`, `Bonded`, `Unbonded`, `Withdrawn` and `StateChanged` events
+    NominationPools,
+    /// Committee management pallet `BanValidators` and elections pallet `ChangeValidators` events
+    Committee,
+    /// Events of the contracts watched by the operator, with a field matching the subscribed account
+    ContractEvents,
+    /// `Transfer` and `Approval` events of the configured PSP22 token contracts
+    Psp22,
+    /// Events listed by the operator, decoded with the dynamic runtime metadata
+    Dynamic,
+}
+
+impl EventKind {
+    /// Returns every supported event kind
+    pub fn all() -> Vec<EventKind> {
+        <EventKind as clap::ValueEnum>::value_variants().to_vec()
+    }
+
+    /// Returns the kind matching the `EventKind` variant of the subscriptions smart contract,
+    /// if it is supported by the service
+    pub fn from_variant_name(name: &str) -> Option<EventKind> {
+        match name {
+            "TransferIn" => Some(EventKind::TransferIn),
+            "TransferOut" => Some(EventKind::TransferOut),
+            "Rewarded" => Some(EventKind::Rewarded),
+            "Slashed" => Some(EventKind::Slashed),
+            "Bonded" => Some(EventKind::Bonded),
+            "Unbonded" => Some(EventKind::Unbonded),
+            "Withdrawn" => Some(EventKind::Withdrawn),
+            "NominationPools" => Some(EventKind::NominationPools),
+            "Committee" => Some(EventKind::Committee),
+            "ContractEvents" => Some(EventKind::ContractEvents),
+            "Psp22" => Some(EventKind::Psp22),
+            "Dynamic" => Some(EventKind::Dynamic),
+            _ => None,
+        }
+    }
+}
+
 /// Events subsription logic
 #[derive(Debug)]
 pub struct Events {
@@ -23,137 +98,728 @@ pub struct Events {
     term: Arc<AtomicBool>,
 
     /// Subscriptions smart contract client
-    active_subscriptions: Arc<Mutex<HashMap<AccountId, Subscription>>>,
+    active_subscriptions: Arc<Mutex<ActiveSubscriptions>>,
+
+    /// Store persisting the last processed block
+    store: Arc<Store>,
+
+    /// Webservice endpoint addresses of the Aleph Zero nodes, used in turn on reconnection
+    node_addresses: Vec<String>,
+
+    /// Decoder of the events of the contracts watched by the operator
+    contract_events: ContractEvents,
+
+    /// Decoder of the events of the configured PSP22 token contracts
+    psp22_tokens: Psp22Tokens,
+
+    /// Decoder of the events listed by the operator
+    dynamic_events: DynamicEvents,
 }
 
 impl Events {
     /// Creates new instance of the events handler
     pub fn new(
         term: Arc<AtomicBool>,
-        active_subscriptions: Arc<Mutex<HashMap<AccountId, Subscription>>>,
+        active_subscriptions: Arc<Mutex<ActiveSubscriptions>>,
+        store: Arc<Store>,
+        node_addresses: Vec<String>,
+        contract_events: ContractEvents,
+        psp22_tokens: Psp22Tokens,
+        dynamic_events: DynamicEvents,
     ) -> Result<Self> {
         Ok(Self {
             term,
             active_subscriptions,
+            store,
+            node_addresses,
+            contract_events,
+            psp22_tokens,
+            dynamic_events,
         })
     }
 
-    /// Sends notification about every transfer event for a given on-chain address
-    pub async fn send_transfer_event_notification(
+    /// Sends notifications about every event of the given kinds through the outbox.
+    /// All kinds are handled from the single stream of finalized blocks. A failure of one kind does not skip
+    /// the other kinds, the block is replayed once all its events are handled.
+    pub async fn send_events_notification(
         &self,
         conn: Connection,
-        notifier: &impl NotificationSender,
+        kinds: &[EventKind],
+        outbox: &Outbox,
+    ) -> Result<()> {
+        let cursor = self.store.cursor(EVENTS_CURSOR)?;
+        let mut blocks = FinalizedBlocks::new(
+            Arc::clone(&self.term),
+            conn.as_client().clone(),
+            self.node_addresses.clone(),
+            cursor,
+        );
+
+        log::info!("aleph-client waiting for events: {:?} ...", kinds);
+
+        while let Some(block) = blocks.next().await {
+            let events = match block.events().await {
+                Ok(events) => events,
+                Err(err) => {
+                    log::error!(
+                        "Failed to obtain events of the block {}: {}",
+                        block.number(),
+                        err
+                    );
+                    blocks.replay(&block);
+                    continue;
+                }
+            };
+            let event_block = EventBlock {
+                number: block.number(),
+                hash: block.hash(),
+            };
+            let metadata = blocks.metadata();
+            let mut failed = false;
+            for event in events.iter() {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::error!(
+                            "Failed to obtain event from the block {}: {:?}",
+                            block.number(),
+                            err
+                        );
+                        continue;
+                    }
+                };
+                let failures = dispatch(kinds, |kind| {
+                    self.handle_event(kind, &block, &event, &metadata, &event_block, outbox)
+                })
+                .await;
+                for (kind, err) in failures {
+                    log::error!(
+                        "Failed to handle {:?} events of the block {}: {:?}",
+                        kind,
+                        block.number(),
+                        err
+                    );
+                    failed = true;
+                }
+            }
+            if failed && blocks.replay(&block) {
+                continue;
+            }
+            self.store
+                .save_cursor(EVENTS_CURSOR, &Cursor::from(&block))?;
+        }
+
+        bail!("Events handling terminated")
+    }
+
+    /// Sends notifications about the event to the subscriptions of the given kind
+    async fn handle_event(
+        &self,
+        kind: EventKind,
+        block: &AlephBlock,
+        event: &EventDetails<AlephConfig>,
+        metadata: &Metadata,
+        event_block: &EventBlock,
+        outbox: &Outbox,
     ) -> Result<()> {
+        match kind {
+            EventKind::TransferIn => self.send_transfer_event_notification(
+                event,
+                TransferDirection::Incoming,
+                event_block,
+                outbox,
+            ),
+            EventKind::TransferOut => self.send_transfer_event_notification(
+                event,
+                TransferDirection::Outgoing,
+                event_block,
+                outbox,
+            ),
+            EventKind::Rewarded => {
+                self.send_rewarded_event_notification(event, event_block, outbox)
+            }
+            EventKind::Slashed => {
+                self.send_slashed_event_notification(event, event_block, outbox)?;
+                self.send_slash_reported_event_notification(event, event_block, outbox)
+            }
+            EventKind::Bonded => self.send_bonded_event_notification(event, event_block, outbox),
+            EventKind::Unbonded => {
+                self.send_unbonded_event_notification(event, event_block, outbox)
+            }
+            EventKind::Withdrawn => {
+                self.send_withdrawn_event_notification(event, event_block, outbox)
+            }
+            EventKind::NominationPools => {
+                self.send_pool_paidout_event_notification(event, event_block, outbox)?;
+                self.send_pool_bonded_event_notification(event, event_block, outbox)?;
+                self.send_pool_unbonded_event_notification(event, event_block, outbox)?;
+                self.send_pool_withdrawn_event_notification(event, event_block, outbox)?;
+                self.send_pool_state_changed_notification(block, event, event_block, outbox)
+                    .await
+            }
+            EventKind::Committee => {
+                self.send_ban_validators_notification(event, event_block, outbox)?;
+                self.send_change_validators_notification(event, event_block, outbox)
+            }
+            EventKind::ContractEvents => {
+                self.send_contract_event_notification(event, event_block, outbox)
+            }
+            EventKind::Psp22 => self.send_psp22_event_notification(event, event_block, outbox),
+            EventKind::Dynamic => {
+                self.send_dynamic_event_notification(event, metadata, event_block, outbox)
+            }
+        }
+    }
+
+    /// Sends notification about transfer event for a given on-chain address,
+    /// to the receiver for incoming and to the sender for outgoing direction
+    fn send_transfer_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        direction: TransferDirection,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        let kind = match direction {
+            TransferDirection::Incoming => EventKind::TransferIn,
+            TransferDirection::Outgoing => EventKind::TransferOut,
+        };
+        let notified_account = |evt: &Transfer| match direction {
+            TransferDirection::Incoming => evt.to.0.clone(),
+            TransferDirection::Outgoing => evt.from.0.clone(),
+        };
         self.send_event_notification(
-            conn,
-            |evt: &Transfer| {
-                let active_subscriptions = self.active_subscriptions.lock().unwrap();
-                active_subscriptions.contains_key(&evt.to.0)
-            },
+            event,
             |evt: &Transfer| crate::notifications::TransferNotification {
                 from_account: evt.from.0.clone(),
                 to_account: evt.to.0.clone(),
                 amount: evt.amount,
+                direction,
             },
-            |evt: &Transfer| -> Result<ChannelHandle> {
-                let active_subscriptions = self.active_subscriptions.lock().unwrap();
-                Ok(ChannelHandle(
-                    active_subscriptions
-                        .get(&evt.to.0)
-                        .ok_or(anyhow::anyhow!("subscription not found"))?
-                        .channel_handle
-                        .clone(),
-                ))
-            },
-            notifier,
+            |evt: &Transfer| self.channel_handles(&notified_account(evt), kind, Some(evt.amount)),
+            event_block,
+            outbox,
         )
-        .await
     }
 
-    /// Sends notification about rewarded events associated with a given stash account
-    pub async fn send_rewarded_event_notification(
+    /// Sends notification about rewarded event associated with a given stash account
+    fn send_rewarded_event_notification(
         &self,
-        conn: Connection,
-        notifier: &impl NotificationSender,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
     ) -> Result<()> {
         self.send_event_notification(
-            conn,
+            event,
+            |evt: &Rewarded| crate::notifications::RewardedNotification {
+                stash_account: evt.stash.0.clone(),
+                amount: evt.amount,
+            },
             |evt: &Rewarded| {
-                let active_subscriptions = self.active_subscriptions.lock().unwrap();
-                active_subscriptions.contains_key(&evt.stash.0)
+                self.channel_handles(&evt.stash.0, EventKind::Rewarded, Some(evt.amount))
             },
-            |evt: &Rewarded| crate::notifications::RewardedNotification {
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about slashed event associated with a given stash account
+    fn send_slashed_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &Slashed| crate::notifications::SlashedNotification {
+                stash_account: evt.staker.0.clone(),
+                amount: evt.amount,
+            },
+            |evt: &Slashed| {
+                self.channel_handles(&evt.staker.0, EventKind::Slashed, Some(evt.amount))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about slash reported for a given validator stash account
+    fn send_slash_reported_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &SlashReported| crate::notifications::SlashReportedNotification {
+                validator_account: evt.validator.0.clone(),
+                fraction: evt.fraction.0,
+                slash_era: evt.slash_era,
+            },
+            |evt: &SlashReported| self.channel_handles(&evt.validator.0, EventKind::Slashed, None),
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about bonded event associated with a given stash account
+    fn send_bonded_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &Bonded| crate::notifications::BondedNotification {
+                stash_account: evt.stash.0.clone(),
+                amount: evt.amount,
+            },
+            |evt: &Bonded| self.channel_handles(&evt.stash.0, EventKind::Bonded, Some(evt.amount)),
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about unbonded event associated with a given stash account
+    fn send_unbonded_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &Unbonded| crate::notifications::UnbondedNotification {
+                stash_account: evt.stash.0.clone(),
+                amount: evt.amount,
+            },
+            |evt: &Unbonded| {
+                self.channel_handles(&evt.stash.0, EventKind::Unbonded, Some(evt.amount))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about withdrawn event associated with a given stash account
+    fn send_withdrawn_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &Withdrawn| crate::notifications::WithdrawnNotification {
                 stash_account: evt.stash.0.clone(),
                 amount: evt.amount,
             },
-            |evt: &Rewarded| -> Result<ChannelHandle> {
-                let active_subscriptions = self.active_subscriptions.lock().unwrap();
-                Ok(ChannelHandle(
-                    active_subscriptions
-                        .get(&evt.stash.0)
-                        .ok_or(anyhow::anyhow!("subscription not found"))?
-                        .channel_handle
-                        .clone(),
-                ))
-            },
-            notifier,
+            |evt: &Withdrawn| {
+                self.channel_handles(&evt.stash.0, EventKind::Withdrawn, Some(evt.amount))
+            },
+            event_block,
+            outbox,
         )
-        .await
     }
 
-    async fn send_event_notification<
+    /// Sends notification about nomination pool paid out event associated with a given member account
+    fn send_pool_paidout_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &pool_events::PaidOut| crate::notifications::PoolPaidOutNotification {
+                member_account: evt.member.0.clone(),
+                pool_id: evt.pool_id,
+                amount: evt.payout,
+            },
+            |evt: &pool_events::PaidOut| {
+                self.channel_handles(&evt.member.0, EventKind::NominationPools, Some(evt.payout))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about nomination pool bonded event associated with a given member account
+    fn send_pool_bonded_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &pool_events::Bonded| crate::notifications::PoolBondedNotification {
+                member_account: evt.member.0.clone(),
+                pool_id: evt.pool_id,
+                amount: evt.bonded,
+                joined: evt.joined,
+            },
+            |evt: &pool_events::Bonded| {
+                self.channel_handles(&evt.member.0, EventKind::NominationPools, Some(evt.bonded))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about nomination pool unbonded event associated with a given member account
+    fn send_pool_unbonded_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &pool_events::Unbonded| crate::notifications::PoolUnbondedNotification {
+                member_account: evt.member.0.clone(),
+                pool_id: evt.pool_id,
+                amount: evt.balance,
+                era: evt.era,
+            },
+            |evt: &pool_events::Unbonded| {
+                self.channel_handles(&evt.member.0, EventKind::NominationPools, Some(evt.balance))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about nomination pool withdrawn event associated with a given member account
+    fn send_pool_withdrawn_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        self.send_event_notification(
+            event,
+            |evt: &pool_events::Withdrawn| crate::notifications::PoolWithdrawnNotification {
+                member_account: evt.member.0.clone(),
+                pool_id: evt.pool_id,
+                amount: evt.balance,
+            },
+            |evt: &pool_events::Withdrawn| {
+                self.channel_handles(&evt.member.0, EventKind::NominationPools, Some(evt.balance))
+            },
+            event_block,
+            outbox,
+        )
+    }
+
+    /// Sends notification about the state change of the nomination pool to every subscribed member of the pool.
+    /// The membership is read from the chain state at the block of the event
+    async fn send_pool_state_changed_notification(
+        &self,
+        block: &AlephBlock,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        let evt = match event.as_event::<pool_events::StateChanged>() {
+            Ok(Some(evt)) => evt,
+            _ => return Ok(()),
+        };
+        let subscribed_accounts = self
+            .active_subscriptions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|subscription| {
+                !subscription.paused
+                    && subscription
+                        .event_kinds
+                        .contains(&EventKind::NominationPools)
+            })
+            .flat_map(|subscription| subscription.accounts().cloned())
+            .collect::<HashSet<_>>();
+
+        let storage = block.storage();
+        for account in subscribed_accounts {
+            let member = storage
+                .fetch(
+                    &aleph_client::api::storage()
+                        .nomination_pools()
+                        .pool_members(Static(account.clone())),
+                )
+                .await
+                .with_context(|| format!("Failed to read pool membership of {}", account))?;
+            if !matches!(member, Some(member) if member.pool_id == evt.pool_id) {
+                continue;
+            }
+
+            let channel_handles = self.channel_handles(&account, EventKind::NominationPools, None);
+            self.enqueue_notification(
+                channel_handles,
+                crate::notifications::PoolStateChangedNotification {
+                    member_account: account.clone(),
+                    pool_id: evt.pool_id,
+                    state: format!("{:?}", evt.new_state),
+                },
+                event,
+                event_block,
+                outbox,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sends notification about the event of a watched contract to every subscribed account found in its fields
+    fn send_contract_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        for (account, notification) in self.contract_events.notifications(event) {
+            let channel_handles = self.channel_handles(&account, EventKind::ContractEvents, None);
+            self.enqueue_notification(channel_handles, notification, event, event_block, outbox)?;
+        }
+        Ok(())
+    }
+
+    /// Sends notification about the PSP22 token transfer or approval to every subscribed account it concerns
+    fn send_psp22_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        for (account, amount, notification) in self.psp22_tokens.notifications(event) {
+            let channel_handles = self.channel_handles(&account, EventKind::Psp22, Some(amount));
+            self.enqueue_notification(channel_handles, notification, event, event_block, outbox)?;
+        }
+        Ok(())
+    }
+
+    /// Sends notification about the event listed by the operator to every subscribed account found in its account fields
+    fn send_dynamic_event_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        metadata: &Metadata,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        for (account, notification) in self.dynamic_events.notifications(event, metadata) {
+            let channel_handles = self.channel_handles(&account, EventKind::Dynamic, None);
+            self.enqueue_notification(channel_handles, notification, event, event_block, outbox)?;
+        }
+        Ok(())
+    }
+
+    /// Sends notification about the ban to every subscribed validator banned from the committee
+    fn send_ban_validators_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        let evt = match event.as_event::<BanValidators>() {
+            Ok(Some(evt)) => evt,
+            _ => return Ok(()),
+        };
+        for (validator, ban_info) in evt.0 {
+            let validator = validator.0;
+            let channel_handles = self.channel_handles(&validator, EventKind::Committee, None);
+            self.enqueue_notification(
+                channel_handles,
+                ValidatorBannedNotification {
+                    validator_account: validator.clone(),
+                    reason: format!("{:?}", ban_info.reason),
+                    start_era: ban_info.start,
+                },
+                event,
+                event_block,
+                outbox,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sends notification to every subscribed validator included in or removed from the set of validators.
+    /// Changes are detected against the set announced by the previous `ChangeValidators` event. The first such event
+    /// after start only records the set, as every validator would be reported as included otherwise
+    fn send_change_validators_notification(
+        &self,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        let evt = match event.as_event::<ChangeValidators>() {
+            Ok(Some(evt)) => evt,
+            _ => return Ok(()),
+        };
+        let validators = evt
+            .0
+            .into_iter()
+            .chain(evt.1)
+            .map(|validator| validator.0)
+            .collect::<HashSet<_>>();
+        // the validators announced by the previous event are persisted, so changes are detected across restarts
+        let previous = match self.store.validators()? {
+            Some(previous) => previous,
+            None => return self.store.save_validators(&validators),
+        };
+
+        let changes = validators
+            .iter()
+            .filter(|v| !previous.contains(v))
+            .map(|v| (v.clone(), true))
+            .chain(
+                previous
+                    .iter()
+                    .filter(|v| !validators.contains(v))
+                    .map(|v| (v.clone(), false)),
+            );
+
+        for (validator, included) in changes {
+            let channel_handles = self.channel_handles(&validator, EventKind::Committee, None);
+            self.enqueue_notification(
+                channel_handles,
+                ValidatorsChangedNotification {
+                    validator_account: validator.clone(),
+                    included,
+                },
+                event,
+                event_block,
+                outbox,
+            )?;
+        }
+        self.store.save_validators(&validators)
+    }
+
+    /// Returns channel handles of every active subscription watching the account and subscribed to the given kind of events.
+    /// Paused subscriptions and subscriptions with a minimum amount for this kind higher than the amount of the event are skipped
+    fn channel_handles(
+        &self,
+        account: &AccountId,
+        kind: EventKind,
+        amount: Option<Balance>,
+    ) -> Vec<ChannelHandle> {
+        let active_subscriptions = self.active_subscriptions.lock().unwrap();
+        let mut channel_handles: Vec<String> = Vec::new();
+        for subscription in active_subscriptions.watching(account) {
+            if subscription.paused || !subscription.event_kinds.contains(&kind) {
+                continue;
+            }
+            if let (Some(amount), Some(min_amount)) = (amount, subscription.min_amounts.get(&kind))
+            {
+                if amount < *min_amount {
+                    continue;
+                }
+            }
+            // the same channel handle may be used by several subscriptions watching the account
+            for channel_handle in subscription.channel_handles.iter() {
+                if !channel_handles.contains(channel_handle) {
+                    channel_handles.push(channel_handle.clone());
+                }
+            }
+        }
+        channel_handles.into_iter().map(ChannelHandle).collect()
+    }
+
+    /// Puts the notification about the event into the outbox, separately for every given channel handle
+    fn enqueue_notification(
+        &self,
+        channel_handles: Vec<ChannelHandle>,
+        notification: impl Into<Notification>,
+        event: &EventDetails<AlephConfig>,
+        event_block: &EventBlock,
+        outbox: &Outbox,
+    ) -> Result<()> {
+        if channel_handles.is_empty() {
+            return Ok(());
+        }
+        let msg = EventNotification {
+            block: *event_block,
+            event_index: event.index(),
+            notification: notification.into(),
+        };
+        log::info!("Queueing notification for event: {}", msg);
+        for channel_handle in channel_handles {
+            outbox.enqueue(&channel_handle, &msg)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes the event as `T` and, if it matches any active subscription, puts a notification about it into the outbox,
+    /// separately for every channel handle of the matching subscriptions, so they are delivered independently
+    fn send_event_notification<
         T: StaticEvent,
-        M: NotificationMessage,
-        P: Fn(&T) -> bool + Send,
+        M: Into<Notification>,
         C: Fn(&T) -> M,
-        H: Fn(&T) -> Result<ChannelHandle>,
+        H: Fn(&T) -> Vec<ChannelHandle>,
     >(
         &self,
-        conn: Connection,
-        predicate: P,
+        event: &EventDetails<AlephConfig>,
         converter: C,
-        channel_handle_extractor: H,
-        notifier: &impl NotificationSender,
+        channel_handles_extractor: H,
+        event_block: &EventBlock,
+        outbox: &Outbox,
     ) -> Result<()> {
-        let mut block_sub = conn
-            .as_client()
-            .blocks()
-            .subscribe_finalized()
-            .await
-            .context("Failed to subscribe to the finalized block stream")?;
+        let evt = match event.as_event::<T>() {
+            Ok(Some(evt)) => evt,
+            _ => return Ok(()),
+        };
+        let channel_handles = channel_handles_extractor(&evt);
+        self.enqueue_notification(channel_handles, converter(&evt), event, event_block, outbox)
+    }
+}
 
-        log::info!("aleph-client waiting for events ...");
+/// Runs the handler of every kind, so a failure of one kind doesn't skip the others.
+/// Returns the kinds whose handler failed, with their errors
+async fn dispatch<F, Fut>(kinds: &[EventKind], mut handler: F) -> Vec<(EventKind, anyhow::Error)>
+where
+    F: FnMut(EventKind) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut failures = vec![];
+    for kind in kinds {
+        if let Err(err) = handler(*kind).await {
+            failures.push((*kind, err));
+        }
+    }
+    failures
+}
 
-        while let Some(Ok(block)) = block_sub.next().await {
-            if self.term.load(std::sync::atomic::Ordering::Relaxed) {
-                bail!("Rewards events terminated")
-            }
-            let events = match block.events().await {
-                Ok(events) => events,
-                _ => continue,
-            };
-            for event in events.iter() {
-                let event = event.context("Failed to obtain event from the block")?;
-                if let Ok(Some(evt)) = event.as_event::<T>() {
-                    if !predicate(&evt) {
-                        continue;
-                    }
-                    let msg = converter(&evt);
-                    let res = notifier
-                        .send_notification(msg.clone(), channel_handle_extractor(&evt)?)
-                        .await;
-                    match res {
-                        Err(err) => log::error!(
-                            "Error sending notification for event: {}, error: {}",
-                            msg.to_string(),
-                            err
-                        ),
-                        Ok(_) => continue,
-                    };
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn failure_of_one_kind_does_not_skip_the_others() {
+        let kinds = [
+            EventKind::TransferIn,
+            EventKind::Rewarded,
+            EventKind::Slashed,
+        ];
+        let mut handled = vec![];
+
+        let failures = dispatch(&kinds, |kind| {
+            handled.push(kind);
+            async move {
+                match kind {
+                    EventKind::Rewarded => Err(anyhow!("store failure")),
+                    _ => Ok(()),
                 }
             }
-        }
+        })
+        .await;
 
-        bail!("No more blocks to proceed")
+        assert_eq!(handled, kinds);
+        assert_eq!(
+            failures.iter().map(|(kind, _)| *kind).collect::<Vec<_>>(),
+            vec![EventKind::Rewarded]
+        );
     }
 }
@@ -1,16 +1,38 @@
 #![feature(trait_alias)]
 
+mod blocks;
 mod cli;
+mod contract_events;
+mod dynamic_events;
 mod events;
 mod notifications;
+mod outbox;
+mod psp22;
+mod store;
 mod subscriptions;
 
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
+use contract_events::ContractEvents;
+use dynamic_events::DynamicEvents;
 use env_logger::Env;
-use events::Events;
+use events::{EventKind, Events};
+use notifications::{
+    discord::DiscordSender,
+    email::{EmailSender, SmtpSettings},
+    matrix::MatrixSender,
+    router::ChannelRouter,
+    slack::SlackSender,
+    telegram::TelegramBot,
+    webhook::WebhookSender,
+};
+use outbox::Outbox;
+use psp22::Psp22Tokens;
 use signal_hook::consts::SIGINT;
 
 #[tokio::main]
@@ -23,52 +45,160 @@ async fn main() -> Result<()> {
     let term = Arc::new(AtomicBool::new(false));
     signal_hook::flag::register(SIGINT, Arc::clone(&term))?;
 
+    log::info!("Opening store: {:?}", cli.store);
+    let store = Arc::new(store::Store::open(&cli.store)?);
+
+    let (kinds, channels) = match cli.commands {
+        cli::Commands::TransferEvent { channels } => (
+            vec![EventKind::TransferIn, EventKind::TransferOut],
+            channels,
+        ),
+        cli::Commands::RewardedEvent { channels } => (vec![EventKind::Rewarded], channels),
+        cli::Commands::SlashedEvent { channels } => (vec![EventKind::Slashed], channels),
+        cli::Commands::PoolEvent { channels } => (vec![EventKind::NominationPools], channels),
+        cli::Commands::CommitteeEvent { channels } => (vec![EventKind::Committee], channels),
+        cli::Commands::ContractEvent { channels } => (vec![EventKind::ContractEvents], channels),
+        cli::Commands::Psp22Event { channels } => (vec![EventKind::Psp22], channels),
+        cli::Commands::DynamicEvent { channels } => (vec![EventKind::Dynamic], channels),
+        cli::Commands::BondingEvent { channels } => (
+            vec![EventKind::Bonded, EventKind::Unbonded, EventKind::Withdrawn],
+            channels,
+        ),
+        cli::Commands::AllEvents { kinds, channels } if kinds.is_empty() => {
+            (EventKind::all(), channels)
+        }
+        cli::Commands::AllEvents { kinds, channels } => (kinds, channels),
+        cli::Commands::DeadLetters { commands } => return handle_dead_letters(&store, commands),
+    };
+
+    let router = build_router(channels)?;
+    log::info!("Notification channels: {:?}", router.channels());
+
     log::info!("Establishing smart contract client...");
     let mut subscriptions = subscriptions::Subscriptions::new(
         Arc::clone(&term),
         cli.sc_address,
-        &cli.node_address,
+        &cli.node_addresses,
         &cli.sc_metadata,
+        Arc::clone(&store),
     )?;
     log::info!("Initializing subscriptions...");
     subscriptions.init_subscriptions().await?;
     log::info!("Subscriptions initialized: {:?}", subscriptions);
 
-    log::info!("Establishing connection...");
-    let conn = aleph_client::Connection::new(&cli.node_address).await;
-    log::info!("Connection is live...");
+    // Events share the node connection with the subscriptions smart contract client
+    let conn = subscriptions.connection();
+
+    let contract_events = ContractEvents::new(&cli.watched_contracts)?;
+    if kinds.contains(&EventKind::ContractEvents) && contract_events.is_empty() {
+        log::warn!("No contract watched, contract events are not captured");
+    }
+
+    let psp22_tokens = Psp22Tokens::new(&conn, &cli.watched_tokens).await?;
+    if kinds.contains(&EventKind::Psp22) && psp22_tokens.is_empty() {
+        log::warn!("No PSP22 token watched, token events are not captured");
+    }
+    log::info!("PSP22 tokens: {:?}", psp22_tokens);
+
+    let dynamic_events = DynamicEvents::new(cli.dynamic_events.clone());
+    if kinds.contains(&EventKind::Dynamic) && dynamic_events.is_empty() {
+        log::warn!("No dynamic event listed, dynamic events are not captured");
+    }
 
     let events = Events::new(
         Arc::clone(&term),
         subscriptions.active_subscriptions.clone(),
+        Arc::clone(&store),
+        cli.node_addresses.clone(),
+        contract_events,
+        psp22_tokens,
+        dynamic_events,
     )?;
 
+    let outbox = Outbox::new(
+        Arc::clone(&term),
+        Arc::clone(&store),
+        cli.max_delivery_attempts,
+    );
+
+    let subscriptions_outbox = outbox.clone();
     let join = tokio::spawn(async move {
         log::info!("Subscriptions smart contract event loop is live...");
-        subscriptions.handle_events().await?;
+        subscriptions.handle_events(&subscriptions_outbox).await?;
         <Result<(), anyhow::Error>>::Ok(())
     });
 
-    match cli.commands {
-        cli::Commands::TransferEvent { targets } => match targets {
-            cli::Targets::Telegram { token } => {
-                let telegram_bot = notifications::telegram::TelegramBot::new(token)?;
-                events
-                    .send_transfer_event_notification(conn, &telegram_bot)
-                    .await?
-            }
-        },
-        cli::Commands::RewardedEvent { targets } => match targets {
-            cli::Targets::Telegram { token } => {
-                let telegram_bot = notifications::telegram::TelegramBot::new(token)?;
-                events
-                    .send_rewarded_event_notification(conn, &telegram_bot)
-                    .await?
-            }
-        },
-    }
+    tokio::try_join!(
+        events.send_events_notification(conn, &kinds, &outbox),
+        outbox.deliver(&router),
+    )?;
 
     join.await??;
 
     Ok(())
 }
+
+/// Creates the router sending notifications through every configured channel
+fn build_router(channels: cli::Channels) -> Result<ChannelRouter> {
+    let timeout = Duration::from_secs(channels.timeout);
+    let mut router = ChannelRouter::new();
+
+    if let Some(token) = channels.telegram_token {
+        router = router.with_telegram(TelegramBot::new(token)?);
+    }
+    if let Some(secret) = channels.webhook_secret {
+        router = router.with_webhook(WebhookSender::new(secret, timeout)?);
+    }
+    if let (Some(host), Some(from)) = (channels.smtp_host, channels.email_from) {
+        let settings = SmtpSettings {
+            host,
+            port: channels.smtp_port,
+            security: channels.smtp_security,
+            credentials: channels.smtp_username.zip(channels.smtp_password),
+            timeout,
+        };
+        router = router.with_email(EmailSender::new(settings, &from)?);
+    }
+    if channels.discord {
+        router = router.with_discord(DiscordSender::new(channels.explorer_url.clone(), timeout)?);
+    }
+    if channels.slack {
+        router = router.with_slack(SlackSender::new(channels.explorer_url.clone(), timeout)?);
+    }
+    if let Some(access_token) = channels.matrix_access_token {
+        router = router.with_matrix(MatrixSender::new(
+            &channels.matrix_homeserver_url,
+            access_token,
+            timeout,
+        )?);
+    }
+
+    if router.channels().is_empty() {
+        bail!("No notification channel configured");
+    }
+    Ok(router)
+}
+
+/// Lists dead letters or moves them back to the outbox
+fn handle_dead_letters(store: &store::Store, commands: cli::DeadLetterCommands) -> Result<()> {
+    match commands {
+        cli::DeadLetterCommands::List => {
+            for dead_letter in store.dead_letters()? {
+                println!(
+                    "id: {}, channel handle: {}, attempts: {}, failed at: {}, last error: {}\n  {}",
+                    dead_letter.id,
+                    dead_letter.channel_handle,
+                    dead_letter.attempts,
+                    dead_letter.failed_at,
+                    dead_letter.last_error,
+                    dead_letter.notification
+                );
+            }
+        }
+        cli::DeadLetterCommands::Redrive { id } => {
+            let moved = store.redrive_dead_letters(id, outbox::now())?;
+            log::info!("Dead letters moved back to the outbox: {}", moved);
+        }
+    }
+    Ok(())
+}
@@ -1,22 +1,91 @@
-use aleph_client::{AccountId, Balance};
+use std::{collections::BTreeMap, time::Duration};
+
+use aleph_client::{AccountId, Balance, BlockHash, BlockNumber};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
+pub mod discord;
+pub mod email;
+mod incoming_webhook;
+pub mod matrix;
+pub mod router;
+pub mod slack;
 pub mod telegram;
+pub mod webhook;
+
+#[cfg(test)]
+pub mod test_server;
 
 /// Formats notification messages
 pub trait FormatToString {
     fn format(&self) -> String;
+
+    /// Formats notification message as a HTML fragment, by default the escaped plain text message
+    fn format_html(&self) -> String {
+        format!("<p>{}</p>", escape_html(&self.format()))
+    }
 }
 
-/// Represents notification about the transfer `to_account`
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Describes notification messages as structured content, e.g. for rich embeds
+pub trait FormatToFields {
+    /// Short title of the notification
+    fn title(&self) -> String;
+
+    /// Named values of the notification, e.g. accounts and amounts
+    fn fields(&self) -> Vec<(String, String)>;
+
+    /// Block the notified event was emitted in, if known
+    fn block(&self) -> Option<EventBlock> {
+        None
+    }
+
+    /// How urgently the subscriber should look at the notification
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
+}
+
+/// Urgency of the notification, e.g. slashes require immediate attention
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Normal,
+    High,
+}
+
+/// Escapes characters with a special meaning in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Direction of the transfer from the subscriber's point of view
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+    /// Tokens transferred to the subscribed account
+    #[default]
+    Incoming,
+    /// Tokens transferred from the subscribed account
+    Outgoing,
+}
+
+/// Represents notification about the transfer `to_account` or `from_account`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TransferNotification {
     /// The account from which the transfer was made
     pub from_account: AccountId,
     /// The account to which transfer was directed
     pub to_account: AccountId,
     /// Amount of tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO    
+    #[serde(with = "balance_as_string")]
     pub amount: Balance,
+    /// Which side of the transfer is notified. Incoming for notifications queued before it was introduced
+    #[serde(default)]
+    pub direction: TransferDirection,
 }
 
 /// Notification must implement display trait to be prinatable
@@ -29,11 +98,53 @@ impl std::fmt::Display for TransferNotification {
 /// Notification must implmenet formating to the string message
 impl FormatToString for TransferNotification {
     fn format(&self) -> String {
-        format!(
-            "New transfer from account {:?}, amount {}",
-            self.from_account,
-            print_with_4_digits(self.amount, 1_000_000_000_000u128) //self.amount as f64 / 1_000_000_000_000_f64
-        )
+        match self.direction {
+            TransferDirection::Incoming => format!(
+                "New transfer from account {:?}, amount {}",
+                self.from_account,
+                print_with_4_digits(self.amount, 1_000_000_000_000u128) //self.amount as f64 / 1_000_000_000_000_f64
+            ),
+            TransferDirection::Outgoing => format!(
+                "Outgoing transfer to account {:?} from your account {:?}, amount {}",
+                self.to_account,
+                self.from_account,
+                print_with_4_digits(self.amount, 1_000_000_000_000u128)
+            ),
+        }
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for TransferNotification {
+    fn title(&self) -> String {
+        match self.direction {
+            TransferDirection::Incoming => "New transfer".to_string(),
+            TransferDirection::Outgoing => "Outgoing transfer".to_string(),
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("From".to_string(), self.from_account.to_string()),
+            ("To".to_string(), self.to_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Formats the token amount given in the smallest token unit with the token decimals, e.g. `1.5` for `1_500_000` and 6 decimals
+fn print_with_decimals(amount: u128, decimals: u8) -> String {
+    let unit = 10u128.saturating_pow(decimals as u32);
+    let frac = format!("{:0width$}", amount % unit, width = decimals as usize);
+    let frac = frac.trim_end_matches('0');
+
+    if frac.is_empty() {
+        format!("{}", amount / unit)
+    } else {
+        format!("{}.{}", amount / unit, frac)
     }
 }
 
@@ -44,15 +155,16 @@ fn print_with_4_digits(a: u128, b: u128) -> String {
     let frac = div % 10000;
     let rest = div / 10000;
 
-    format!("{}.{:#03}", rest, frac)
+    format!("{}.{:04}", rest, frac)
 }
 
 /// Represents notification about the nominator reward
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct RewardedNotification {
     /// The account used by the nominator for stashing
     pub stash_account: AccountId,
     /// Amount of reward: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO        
+    #[serde(with = "balance_as_string")]
     pub amount: Balance,
 }
 
@@ -74,19 +186,1372 @@ impl FormatToString for RewardedNotification {
     }
 }
 
-/// Alias for bounded notification message. This is an experimental feature that must be enabled with #![feature(trait_alias)]
-pub trait NotificationMessage = Clone + FormatToString + std::fmt::Display + Send;
+/// Notification must implement formatting to the structured content
+impl FormatToFields for RewardedNotification {
+    fn title(&self) -> String {
+        "New reward for nominating".to_string()
+    }
 
-/// Represents channel handle convertible to e.g. Telegram user/chat id
-pub struct ChannelHandle(pub String);
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Stash".to_string(), self.stash_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
 
-/// Sending various notifications
-#[async_trait::async_trait]
-pub trait NotificationSender {
-    /// Sends notification about on-chain event
-    async fn send_notification<T: NotificationMessage>(
-        &self,
-        msg: T,
-        channel_handle: ChannelHandle,
-    ) -> Result<()>;
+/// Represents notification about the bonding of the tokens for staking
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BondedNotification {
+    /// The stash account of the validator or nominator
+    pub stash_account: AccountId,
+    /// Amount of bonded tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for BondedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for BondedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Tokens of account {:?} bonded for staking, amount {}",
+            self.stash_account,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128)
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for BondedNotification {
+    fn title(&self) -> String {
+        "Tokens bonded".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Stash".to_string(), self.stash_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about the unbonding of the staked tokens
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UnbondedNotification {
+    /// The stash account of the validator or nominator
+    pub stash_account: AccountId,
+    /// Amount of unbonded tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for UnbondedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for UnbondedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Tokens of account {:?} unbonded, amount {}. They become withdrawable after the bonding duration",
+            self.stash_account,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128)
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for UnbondedNotification {
+    fn title(&self) -> String {
+        "Tokens unbonded".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Stash".to_string(), self.stash_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about the withdrawal of the unbonded tokens
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawnNotification {
+    /// The stash account of the validator or nominator
+    pub stash_account: AccountId,
+    /// Amount of withdrawn tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for WithdrawnNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for WithdrawnNotification {
+    fn format(&self) -> String {
+        format!(
+            "Unbonded tokens of account {:?} withdrawn, amount {}",
+            self.stash_account,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128)
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for WithdrawnNotification {
+    fn title(&self) -> String {
+        "Tokens withdrawn".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Stash".to_string(), self.stash_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about the reward paid out to the nomination pool member
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PoolPaidOutNotification {
+    /// The account of the pool member
+    pub member_account: AccountId,
+    /// Identifier of the nomination pool
+    pub pool_id: u32,
+    /// Amount of reward: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for PoolPaidOutNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for PoolPaidOutNotification {
+    fn format(&self) -> String {
+        format!(
+            "New reward from nomination pool {} for member {:?}, amount {}",
+            self.pool_id,
+            self.member_account,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128),
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for PoolPaidOutNotification {
+    fn title(&self) -> String {
+        "New nomination pool reward".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Member".to_string(), self.member_account.to_string()),
+            ("Pool".to_string(), self.pool_id.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about tokens bonded by the nomination pool member
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PoolBondedNotification {
+    /// The account of the pool member
+    pub member_account: AccountId,
+    /// Identifier of the nomination pool
+    pub pool_id: u32,
+    /// Amount of bonded tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+    /// Whether the member joined the pool with this bond
+    pub joined: bool,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for PoolBondedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for PoolBondedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Tokens of member {:?} bonded in nomination pool {}, amount {}{}",
+            self.member_account,
+            self.pool_id,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            if self.joined { ", joined the pool" } else { "" },
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for PoolBondedNotification {
+    fn title(&self) -> String {
+        "Tokens bonded in nomination pool".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Member".to_string(), self.member_account.to_string()),
+            ("Pool".to_string(), self.pool_id.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+            ("Joined".to_string(), self.joined.to_string()),
+        ]
+    }
+}
+
+/// Represents notification about tokens unbonded by the nomination pool member
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PoolUnbondedNotification {
+    /// The account of the pool member
+    pub member_account: AccountId,
+    /// Identifier of the nomination pool
+    pub pool_id: u32,
+    /// Amount of unbonded tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+    /// Era in which the tokens become withdrawable
+    pub era: u32,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for PoolUnbondedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for PoolUnbondedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Tokens of member {:?} unbonded from nomination pool {}, amount {}. They become withdrawable in era {}",
+            self.member_account,
+            self.pool_id,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            self.era,
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for PoolUnbondedNotification {
+    fn title(&self) -> String {
+        "Tokens unbonded from nomination pool".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Member".to_string(), self.member_account.to_string()),
+            ("Pool".to_string(), self.pool_id.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+            ("Withdrawable in era".to_string(), self.era.to_string()),
+        ]
+    }
+}
+
+/// Represents notification about unbonded tokens withdrawn by the nomination pool member
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PoolWithdrawnNotification {
+    /// The account of the pool member
+    pub member_account: AccountId,
+    /// Identifier of the nomination pool
+    pub pool_id: u32,
+    /// Amount of withdrawn tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for PoolWithdrawnNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for PoolWithdrawnNotification {
+    fn format(&self) -> String {
+        format!(
+            "Unbonded tokens of member {:?} withdrawn from nomination pool {}, amount {}",
+            self.member_account,
+            self.pool_id,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128),
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for PoolWithdrawnNotification {
+    fn title(&self) -> String {
+        "Tokens withdrawn from nomination pool".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Member".to_string(), self.member_account.to_string()),
+            ("Pool".to_string(), self.pool_id.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about the state change of the nomination pool the subscriber is a member of
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PoolStateChangedNotification {
+    /// The account of the pool member
+    pub member_account: AccountId,
+    /// Identifier of the nomination pool
+    pub pool_id: u32,
+    /// New state of the pool, e.g. `Open`, `Blocked` or `Destroying`
+    pub state: String,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for PoolStateChangedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for PoolStateChangedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Nomination pool {} of member {:?} changed state to {}",
+            self.pool_id, self.member_account, self.state
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for PoolStateChangedNotification {
+    fn title(&self) -> String {
+        "Nomination pool state changed".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Member".to_string(), self.member_account.to_string()),
+            ("Pool".to_string(), self.pool_id.to_string()),
+            ("State".to_string(), self.state.clone()),
+        ]
+    }
+}
+
+/// Represents notification about the validator banned from the committee, e.g. for insufficient uptime
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorBannedNotification {
+    /// The account of the banned validator
+    pub validator_account: AccountId,
+    /// Reason of the ban
+    pub reason: String,
+    /// Era the ban starts in
+    pub start_era: u32,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for ValidatorBannedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for ValidatorBannedNotification {
+    fn format(&self) -> String {
+        format!(
+            "HIGH PRIORITY: validator {:?} banned from the committee starting from era {}, reason: {}",
+            self.validator_account, self.start_era, self.reason
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for ValidatorBannedNotification {
+    fn title(&self) -> String {
+        "Validator banned".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Validator".to_string(), self.validator_account.to_string()),
+            ("Start era".to_string(), self.start_era.to_string()),
+            ("Reason".to_string(), self.reason.clone()),
+        ]
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::High
+    }
+}
+
+/// Represents notification about the validator added to or removed from the set of validators elected to the committee
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorsChangedNotification {
+    /// The account of the validator
+    pub validator_account: AccountId,
+    /// Whether the validator is in the new set of validators
+    pub included: bool,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for ValidatorsChangedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for ValidatorsChangedNotification {
+    fn format(&self) -> String {
+        if self.included {
+            format!(
+                "Validator {:?} included in the set of validators",
+                self.validator_account
+            )
+        } else {
+            format!(
+                "HIGH PRIORITY: validator {:?} removed from the set of validators",
+                self.validator_account
+            )
+        }
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for ValidatorsChangedNotification {
+    fn title(&self) -> String {
+        if self.included {
+            "Validator included".to_string()
+        } else {
+            "Validator removed".to_string()
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![("Validator".to_string(), self.validator_account.to_string())]
+    }
+
+    fn priority(&self) -> Priority {
+        if self.included {
+            Priority::Normal
+        } else {
+            Priority::High
+        }
+    }
+}
+
+/// Represents notification about the transfer of PSP22 tokens `to_account` or `from_account`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Psp22TransferNotification {
+    /// The token contract
+    pub token: AccountId,
+    /// Symbol of the token
+    pub symbol: String,
+    /// Number of decimals of the token amounts
+    pub decimals: u8,
+    /// The account from which the transfer was made. Not set when the tokens were minted
+    pub from_account: Option<AccountId>,
+    /// The account to which transfer was directed. Not set when the tokens were burned
+    pub to_account: Option<AccountId>,
+    /// Amount of tokens: unit is the smallest token unit
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+    /// Which side of the transfer is notified
+    pub direction: TransferDirection,
+}
+
+impl Psp22TransferNotification {
+    fn amount_with_symbol(&self) -> String {
+        format!(
+            "{} {}",
+            print_with_decimals(self.amount, self.decimals),
+            self.symbol
+        )
+    }
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for Psp22TransferNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for Psp22TransferNotification {
+    fn format(&self) -> String {
+        match (self.direction, &self.from_account, &self.to_account) {
+            (TransferDirection::Incoming, Some(from), _) => format!(
+                "New {} transfer from account {:?}, amount {}",
+                self.symbol,
+                from,
+                self.amount_with_symbol()
+            ),
+            (TransferDirection::Incoming, None, _) => format!(
+                "New {} tokens minted, amount {}",
+                self.symbol,
+                self.amount_with_symbol()
+            ),
+            (TransferDirection::Outgoing, _, Some(to)) => format!(
+                "Outgoing {} transfer to account {:?}, amount {}",
+                self.symbol,
+                to,
+                self.amount_with_symbol()
+            ),
+            (TransferDirection::Outgoing, _, None) => format!(
+                "{} tokens burned, amount {}",
+                self.symbol,
+                self.amount_with_symbol()
+            ),
+        }
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for Psp22TransferNotification {
+    fn title(&self) -> String {
+        match self.direction {
+            TransferDirection::Incoming => format!("New {} transfer", self.symbol),
+            TransferDirection::Outgoing => format!("Outgoing {} transfer", self.symbol),
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        let account = |account: &Option<AccountId>| {
+            account
+                .as_ref()
+                .map_or("-".to_string(), |account| account.to_string())
+        };
+        vec![
+            ("Token".to_string(), self.token.to_string()),
+            ("From".to_string(), account(&self.from_account)),
+            ("To".to_string(), account(&self.to_account)),
+            ("Amount".to_string(), self.amount_with_symbol()),
+        ]
+    }
+}
+
+/// Represents notification about the approval of PSP22 tokens of `owner_account` to be spent by `spender_account`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Psp22ApprovalNotification {
+    /// The token contract
+    pub token: AccountId,
+    /// Symbol of the token
+    pub symbol: String,
+    /// Number of decimals of the token amounts
+    pub decimals: u8,
+    /// The account whose tokens are approved
+    pub owner_account: AccountId,
+    /// The account allowed to spend the tokens
+    pub spender_account: AccountId,
+    /// Approved amount of tokens: unit is the smallest token unit
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for Psp22ApprovalNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for Psp22ApprovalNotification {
+    fn format(&self) -> String {
+        format!(
+            "Account {:?} allowed to spend {} {} of account {:?}",
+            self.spender_account,
+            print_with_decimals(self.amount, self.decimals),
+            self.symbol,
+            self.owner_account
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for Psp22ApprovalNotification {
+    fn title(&self) -> String {
+        format!("New {} approval", self.symbol)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Token".to_string(), self.token.to_string()),
+            ("Owner".to_string(), self.owner_account.to_string()),
+            ("Spender".to_string(), self.spender_account.to_string()),
+            (
+                "Amount".to_string(),
+                format!(
+                    "{} {}",
+                    print_with_decimals(self.amount, self.decimals),
+                    self.symbol
+                ),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about the event listed by the operator and decoded with the dynamic runtime metadata
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DynamicEventNotification {
+    /// Name of the pallet which emitted the event
+    pub pallet: String,
+    /// Name of the event
+    pub event_name: String,
+    /// The subscribed account found in the event account fields
+    pub account: AccountId,
+    /// Decoded event fields by name
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for DynamicEventNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for DynamicEventNotification {
+    fn format(&self) -> String {
+        format!(
+            "New {}.{} event for account {:?}: {}",
+            self.pallet,
+            self.event_name,
+            self.account,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for DynamicEventNotification {
+    fn title(&self) -> String {
+        format!("New {}.{} event", self.pallet, self.event_name)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        self.fields.clone().into_iter().collect()
+    }
+}
+
+/// Represents notification about the event of a contract watched by the operator, e.g. PSP22 token or DEX pool
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ContractEventNotification {
+    /// The contract which emitted the event
+    pub contract: AccountId,
+    /// Name of the event, as in the contract's metadata
+    pub event_name: String,
+    /// The subscribed account found in the event fields
+    pub account: AccountId,
+    /// Decoded event fields by name
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for ContractEventNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for ContractEventNotification {
+    fn format(&self) -> String {
+        format!(
+            "New {} event of contract {:?} for account {:?}: {}",
+            self.event_name,
+            self.contract,
+            self.account,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for ContractEventNotification {
+    fn title(&self) -> String {
+        format!("New {} contract event", self.event_name)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![("Contract".to_string(), self.contract.to_string())];
+        fields.extend(self.fields.clone());
+        fields
+    }
+}
+
+/// Represents notification about the slash of the staked tokens
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SlashedNotification {
+    /// The stash account of the slashed validator or nominator
+    pub stash_account: AccountId,
+    /// Amount of slashed tokens: unit is the smallest token unit, e.g. 1_000_000_000_000 = 1DZERO
+    #[serde(with = "balance_as_string")]
+    pub amount: Balance,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for SlashedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for SlashedNotification {
+    fn format(&self) -> String {
+        format!(
+            "HIGH PRIORITY: stake of account {:?} slashed, amount {}",
+            self.stash_account,
+            print_with_4_digits(self.amount, 1_000_000_000_000u128)
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for SlashedNotification {
+    fn title(&self) -> String {
+        "Stake slashed".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Stash".to_string(), self.stash_account.to_string()),
+            (
+                "Amount".to_string(),
+                print_with_4_digits(self.amount, 1_000_000_000_000u128),
+            ),
+        ]
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::High
+    }
+}
+
+/// Represents notification about the validator slash reported to be applied in a future era
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SlashReportedNotification {
+    /// The stash account of the reported validator
+    pub validator_account: AccountId,
+    /// Slashed fraction of the stake, in parts per billion
+    pub fraction: u32,
+    /// Era in which the offence took place
+    pub slash_era: u32,
+}
+
+impl SlashReportedNotification {
+    fn fraction_as_percent(&self) -> String {
+        print_with_4_digits(self.fraction as u128 * 100, 1_000_000_000u128)
+    }
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for SlashReportedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for SlashReportedNotification {
+    fn format(&self) -> String {
+        format!(
+            "HIGH PRIORITY: slash of validator {:?} reported for era {}, fraction {}%",
+            self.validator_account,
+            self.slash_era,
+            self.fraction_as_percent()
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for SlashReportedNotification {
+    fn title(&self) -> String {
+        "Slash reported".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Validator".to_string(), self.validator_account.to_string()),
+            ("Era".to_string(), self.slash_era.to_string()),
+            (
+                "Fraction".to_string(),
+                format!("{}%", self.fraction_as_percent()),
+            ),
+        ]
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::High
+    }
+}
+
+/// Represents confirmation of the subscription extended by the subscriber
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionExtendedNotification {
+    /// The account of the extended subscription
+    pub subscription_account: AccountId,
+    /// Number of payment intervals added to the subscription
+    pub intervals: u32,
+    /// Number of declared payment intervals after the extension
+    pub declared_payment_intervals: u32,
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for SubscriptionExtendedNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for SubscriptionExtendedNotification {
+    fn format(&self) -> String {
+        format!(
+            "Subscription of {:?} extended by {} intervals, {} intervals declared in total",
+            self.subscription_account, self.intervals, self.declared_payment_intervals
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for SubscriptionExtendedNotification {
+    fn title(&self) -> String {
+        "Subscription extended".to_string()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            ("Account".to_string(), self.subscription_account.to_string()),
+            ("Added intervals".to_string(), self.intervals.to_string()),
+            (
+                "Declared intervals".to_string(),
+                self.declared_payment_intervals.to_string(),
+            ),
+        ]
+    }
+}
+
+/// Represents notification about any of the supported on-chain events
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Notification {
+    Transfer(TransferNotification),
+    Rewarded(RewardedNotification),
+    Slashed(SlashedNotification),
+    SlashReported(SlashReportedNotification),
+    Bonded(BondedNotification),
+    Unbonded(UnbondedNotification),
+    Withdrawn(WithdrawnNotification),
+    PoolPaidOut(PoolPaidOutNotification),
+    PoolBonded(PoolBondedNotification),
+    PoolUnbonded(PoolUnbondedNotification),
+    PoolWithdrawn(PoolWithdrawnNotification),
+    PoolStateChanged(PoolStateChangedNotification),
+    ValidatorBanned(ValidatorBannedNotification),
+    ValidatorsChanged(ValidatorsChangedNotification),
+    ContractEvent(ContractEventNotification),
+    Psp22Transfer(Psp22TransferNotification),
+    Psp22Approval(Psp22ApprovalNotification),
+    Dynamic(DynamicEventNotification),
+    SubscriptionExtended(SubscriptionExtendedNotification),
+}
+
+impl Notification {
+    /// Returns name of the notification kind, the same as its serialized `kind` tag
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::Transfer(_) => "transfer",
+            Notification::Rewarded(_) => "rewarded",
+            Notification::Slashed(_) => "slashed",
+            Notification::SlashReported(_) => "slash_reported",
+            Notification::Bonded(_) => "bonded",
+            Notification::Unbonded(_) => "unbonded",
+            Notification::Withdrawn(_) => "withdrawn",
+            Notification::PoolPaidOut(_) => "pool_paid_out",
+            Notification::PoolBonded(_) => "pool_bonded",
+            Notification::PoolUnbonded(_) => "pool_unbonded",
+            Notification::PoolWithdrawn(_) => "pool_withdrawn",
+            Notification::PoolStateChanged(_) => "pool_state_changed",
+            Notification::ValidatorBanned(_) => "validator_banned",
+            Notification::ValidatorsChanged(_) => "validators_changed",
+            Notification::ContractEvent(_) => "contract_event",
+            Notification::Psp22Transfer(_) => "psp22_transfer",
+            Notification::Psp22Approval(_) => "psp22_approval",
+            Notification::Dynamic(_) => "dynamic",
+            Notification::SubscriptionExtended(_) => "subscription_extended",
+        }
+    }
+
+    /// Returns the account the notification is about. Not set for the minted or burned side of a PSP22 transfer
+    pub fn account(&self) -> Option<&AccountId> {
+        match self {
+            Notification::Transfer(n) => match n.direction {
+                TransferDirection::Incoming => Some(&n.to_account),
+                TransferDirection::Outgoing => Some(&n.from_account),
+            },
+            Notification::Rewarded(n) => Some(&n.stash_account),
+            Notification::Slashed(n) => Some(&n.stash_account),
+            Notification::SlashReported(n) => Some(&n.validator_account),
+            Notification::Bonded(n) => Some(&n.stash_account),
+            Notification::Unbonded(n) => Some(&n.stash_account),
+            Notification::Withdrawn(n) => Some(&n.stash_account),
+            Notification::PoolPaidOut(n) => Some(&n.member_account),
+            Notification::PoolBonded(n) => Some(&n.member_account),
+            Notification::PoolUnbonded(n) => Some(&n.member_account),
+            Notification::PoolWithdrawn(n) => Some(&n.member_account),
+            Notification::PoolStateChanged(n) => Some(&n.member_account),
+            Notification::ValidatorBanned(n) => Some(&n.validator_account),
+            Notification::ValidatorsChanged(n) => Some(&n.validator_account),
+            Notification::ContractEvent(n) => Some(&n.account),
+            Notification::Psp22Transfer(n) => match n.direction {
+                TransferDirection::Incoming => n.to_account.as_ref(),
+                TransferDirection::Outgoing => n.from_account.as_ref(),
+            },
+            Notification::Psp22Approval(n) => Some(&n.owner_account),
+            Notification::Dynamic(n) => Some(&n.account),
+            Notification::SubscriptionExtended(n) => Some(&n.subscription_account),
+        }
+    }
+}
+
+impl From<TransferNotification> for Notification {
+    fn from(value: TransferNotification) -> Self {
+        Notification::Transfer(value)
+    }
+}
+
+impl From<RewardedNotification> for Notification {
+    fn from(value: RewardedNotification) -> Self {
+        Notification::Rewarded(value)
+    }
+}
+
+impl From<SlashedNotification> for Notification {
+    fn from(value: SlashedNotification) -> Self {
+        Notification::Slashed(value)
+    }
+}
+
+impl From<SlashReportedNotification> for Notification {
+    fn from(value: SlashReportedNotification) -> Self {
+        Notification::SlashReported(value)
+    }
+}
+
+impl From<BondedNotification> for Notification {
+    fn from(value: BondedNotification) -> Self {
+        Notification::Bonded(value)
+    }
+}
+
+impl From<UnbondedNotification> for Notification {
+    fn from(value: UnbondedNotification) -> Self {
+        Notification::Unbonded(value)
+    }
+}
+
+impl From<WithdrawnNotification> for Notification {
+    fn from(value: WithdrawnNotification) -> Self {
+        Notification::Withdrawn(value)
+    }
+}
+
+impl From<PoolPaidOutNotification> for Notification {
+    fn from(value: PoolPaidOutNotification) -> Self {
+        Notification::PoolPaidOut(value)
+    }
+}
+
+impl From<PoolBondedNotification> for Notification {
+    fn from(value: PoolBondedNotification) -> Self {
+        Notification::PoolBonded(value)
+    }
+}
+
+impl From<PoolUnbondedNotification> for Notification {
+    fn from(value: PoolUnbondedNotification) -> Self {
+        Notification::PoolUnbonded(value)
+    }
+}
+
+impl From<PoolWithdrawnNotification> for Notification {
+    fn from(value: PoolWithdrawnNotification) -> Self {
+        Notification::PoolWithdrawn(value)
+    }
+}
+
+impl From<PoolStateChangedNotification> for Notification {
+    fn from(value: PoolStateChangedNotification) -> Self {
+        Notification::PoolStateChanged(value)
+    }
+}
+
+impl From<ValidatorBannedNotification> for Notification {
+    fn from(value: ValidatorBannedNotification) -> Self {
+        Notification::ValidatorBanned(value)
+    }
+}
+
+impl From<ValidatorsChangedNotification> for Notification {
+    fn from(value: ValidatorsChangedNotification) -> Self {
+        Notification::ValidatorsChanged(value)
+    }
+}
+
+impl From<ContractEventNotification> for Notification {
+    fn from(value: ContractEventNotification) -> Self {
+        Notification::ContractEvent(value)
+    }
+}
+
+impl From<Psp22TransferNotification> for Notification {
+    fn from(value: Psp22TransferNotification) -> Self {
+        Notification::Psp22Transfer(value)
+    }
+}
+
+impl From<Psp22ApprovalNotification> for Notification {
+    fn from(value: Psp22ApprovalNotification) -> Self {
+        Notification::Psp22Approval(value)
+    }
+}
+
+impl From<DynamicEventNotification> for Notification {
+    fn from(value: DynamicEventNotification) -> Self {
+        Notification::Dynamic(value)
+    }
+}
+
+impl From<SubscriptionExtendedNotification> for Notification {
+    fn from(value: SubscriptionExtendedNotification) -> Self {
+        Notification::SubscriptionExtended(value)
+    }
+}
+
+/// Formatted content of a notification of any kind
+trait NotificationContent: std::fmt::Display + FormatToString + FormatToFields {}
+
+impl<T: std::fmt::Display + FormatToString + FormatToFields> NotificationContent for T {}
+
+impl Notification {
+    /// Returns the notification of the specific kind, which the formatting is delegated to
+    fn content(&self) -> &dyn NotificationContent {
+        match self {
+            Notification::Transfer(n) => n,
+            Notification::Rewarded(n) => n,
+            Notification::Slashed(n) => n,
+            Notification::SlashReported(n) => n,
+            Notification::Bonded(n) => n,
+            Notification::Unbonded(n) => n,
+            Notification::Withdrawn(n) => n,
+            Notification::PoolPaidOut(n) => n,
+            Notification::PoolBonded(n) => n,
+            Notification::PoolUnbonded(n) => n,
+            Notification::PoolWithdrawn(n) => n,
+            Notification::PoolStateChanged(n) => n,
+            Notification::ValidatorBanned(n) => n,
+            Notification::ValidatorsChanged(n) => n,
+            Notification::ContractEvent(n) => n,
+            Notification::Psp22Transfer(n) => n,
+            Notification::Psp22Approval(n) => n,
+            Notification::Dynamic(n) => n,
+            Notification::SubscriptionExtended(n) => n,
+        }
+    }
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.content().fmt(f)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for Notification {
+    fn format(&self) -> String {
+        self.content().format()
+    }
+
+    fn format_html(&self) -> String {
+        self.content().format_html()
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for Notification {
+    fn title(&self) -> String {
+        self.content().title()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        self.content().fields()
+    }
+
+    fn priority(&self) -> Priority {
+        self.content().priority()
+    }
+}
+
+/// Represents finalized block the on-chain event was emitted in
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventBlock {
+    /// Number of the block
+    pub number: BlockNumber,
+    /// Hash of the block
+    pub hash: BlockHash,
+}
+
+/// Represents notification about the on-chain event together with the event position in the chain.
+/// Persisted in the outbox until delivered
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventNotification {
+    /// Block the event was emitted in
+    pub block: EventBlock,
+    /// Index of the event in the block
+    pub event_index: u32,
+    /// Notification about the event
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+impl EventNotification {
+    /// Returns identifier unique for every on-chain event, notification kind and notified account.
+    /// Every account involved in the event, and both sides of a transfer, are notified separately,
+    /// even through the same channel handle
+    pub fn event_id(&self) -> String {
+        let mut event_id = format!(
+            "{}:{}:{}",
+            self.block.number,
+            self.event_index,
+            self.notification.kind()
+        );
+        if let Some(account) = self.notification.account() {
+            event_id.push_str(&format!(":{}", account));
+        }
+        match &self.notification {
+            Notification::Transfer(TransferNotification {
+                direction: TransferDirection::Outgoing,
+                ..
+            })
+            | Notification::Psp22Transfer(Psp22TransferNotification {
+                direction: TransferDirection::Outgoing,
+                ..
+            }) => format!("{}:outgoing", event_id),
+            _ => event_id,
+        }
+    }
+}
+
+/// Notification must implement display trait to be printable
+impl std::fmt::Display for EventNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at block {}", self.notification, self.block.number)
+    }
+}
+
+/// Notification must implement formating to the string message
+impl FormatToString for EventNotification {
+    fn format(&self) -> String {
+        self.notification.format()
+    }
+
+    fn format_html(&self) -> String {
+        format!(
+            "{}<p>Block {} ({:?}), event {}</p>",
+            self.notification.format_html(),
+            self.block.number,
+            self.block.hash,
+            self.event_index
+        )
+    }
+}
+
+/// Notification must implement formatting to the structured content
+impl FormatToFields for EventNotification {
+    fn title(&self) -> String {
+        self.notification.title()
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        self.notification.fields()
+    }
+
+    fn block(&self) -> Option<EventBlock> {
+        Some(self.block)
+    }
+
+    fn priority(&self) -> Priority {
+        self.notification.priority()
+    }
+}
+
+/// Serializes amounts of tokens as strings. They don't fit into json numbers
+mod balance_as_string {
+    use aleph_client::Balance;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(amount: &Balance, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(amount)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Alias for bounded notification message. This is an experimental feature that must be enabled with #![feature(trait_alias)]
+pub trait NotificationMessage =
+    Clone + FormatToString + FormatToFields + std::fmt::Display + Serialize + Send;
+
+/// Represents channel handle convertible to e.g. Telegram user/chat id
+pub struct ChannelHandle(pub String);
+
+/// Error returned by a notification sender when the notification can never be delivered,
+/// e.g. the channel handle is invalid. Such notifications are not retried
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PermanentError(pub String);
+
+impl std::fmt::Display for PermanentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PermanentError {}
+
+/// Error returned by a notification sender when the channel asks to wait before the next attempt,
+/// e.g. it is rate limited. Such notifications are retried after the given delay without counting the failed attempt
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RetryAfter {
+    /// Time to wait before the next attempt
+    pub delay: Duration,
+    /// Why the channel asks to wait
+    pub reason: String,
+}
+
+impl std::fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, retry after {:?}", self.reason, self.delay)
+    }
+}
+
+impl std::error::Error for RetryAfter {}
+
+/// Sending various notifications
+#[async_trait::async_trait]
+pub trait NotificationSender {
+    /// Sends notification about on-chain event
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slash_reported(fraction: u32) -> SlashReportedNotification {
+        SlashReportedNotification {
+            validator_account: AccountId::from([1; 32]),
+            fraction,
+            slash_era: 12,
+        }
+    }
+
+    #[test]
+    fn print_with_4_digits_pads_fraction() {
+        assert_eq!(
+            print_with_4_digits(1_500_000_000_000, 1_000_000_000_000),
+            "1.5000"
+        );
+        assert_eq!(
+            print_with_4_digits(50_000_000_000, 1_000_000_000_000),
+            "0.0500"
+        );
+        assert_eq!(
+            print_with_4_digits(100_000_000, 1_000_000_000_000),
+            "0.0001"
+        );
+        assert_eq!(
+            print_with_4_digits(12_000_000_000_000, 1_000_000_000_000),
+            "12.0000"
+        );
+    }
+
+    #[test]
+    fn fraction_as_percent_formats_small_fractions() {
+        assert_eq!(slash_reported(500_000).fraction_as_percent(), "0.0500");
+        assert_eq!(slash_reported(1_000).fraction_as_percent(), "0.0001");
+        assert_eq!(slash_reported(1).fraction_as_percent(), "0.0000");
+        assert_eq!(slash_reported(100_000_000).fraction_as_percent(), "10.0000");
+        assert_eq!(
+            slash_reported(1_000_000_000).fraction_as_percent(),
+            "100.0000"
+        );
+    }
 }
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::json;
+
+use super::{
+    incoming_webhook::{block_link, IncomingWebhook},
+    ChannelHandle, NotificationMessage, NotificationSender, Priority,
+};
+
+/// Prefix of the channel handles addressing Discord incoming webhooks,
+/// e.g. `discord:https://discord.com/api/webhooks/<id>/<token>`
+pub const DISCORD_PREFIX: &str = "discord:";
+
+/// Color of the embed side bar
+const EMBED_COLOR: u32 = 0x00ccab;
+
+/// Color of the embed side bar of high priority notifications
+const HIGH_PRIORITY_EMBED_COLOR: u32 = 0xe53935;
+
+/// A client posting notifications as rich embeds to Discord incoming webhooks
+#[derive(Clone)]
+pub struct DiscordSender {
+    /// Client of the incoming webhooks respecting Discord rate limits
+    webhook: IncomingWebhook,
+
+    /// Base URL of the block explorer, used to link the block of the event
+    explorer_url: String,
+}
+
+impl DiscordSender {
+    /// Creates new instance of the Discord sender
+    pub fn new(explorer_url: String, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            webhook: IncomingWebhook::new("Discord", timeout)?,
+            explorer_url,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for DiscordSender {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let url = self
+            .webhook
+            .parse_channel_handle(channel_handle, DISCORD_PREFIX)?;
+
+        log::info!("Sending message to Discord: {}", msg);
+
+        let mut fields = msg
+            .fields()
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+            .collect::<Vec<_>>();
+        let color = match msg.priority() {
+            Priority::Normal => EMBED_COLOR,
+            Priority::High => HIGH_PRIORITY_EMBED_COLOR,
+        };
+        let mut embed = json!({
+            "title": msg.title(),
+            "description": msg.format(),
+            "color": color,
+        });
+        if let Some(block) = msg.block() {
+            let link = block_link(&self.explorer_url, &block);
+            fields.push(json!({
+                "name": "Block",
+                "value": format!("[{}]({})", block.number, link),
+                "inline": true,
+            }));
+            embed["url"] = json!(link);
+        }
+        embed["fields"] = json!(fields);
+
+        self.webhook.post(&url, &json!({ "embeds": [embed] })).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{
+        test_server::{notification, TestServer},
+        FormatToFields, FormatToString,
+    };
+
+    const EXPLORER_URL: &str = "https://explorer.example.com/block";
+
+    #[tokio::test]
+    async fn posts_embed() {
+        let server = TestServer::start(vec![]).await;
+
+        DiscordSender::new(EXPLORER_URL.to_string(), Duration::from_secs(5))
+            .unwrap()
+            .send_notification(
+                notification(),
+                ChannelHandle(format!("{}{}/hook", DISCORD_PREFIX, server.url)),
+            )
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/hook");
+
+        let payload = requests[0].json();
+        let embeds = payload["embeds"].as_array().unwrap();
+        assert_eq!(embeds.len(), 1);
+        let embed = &embeds[0];
+        assert_eq!(embed["title"], notification().title());
+        assert_eq!(embed["description"], notification().format());
+        // slashes are high priority
+        assert_eq!(embed["color"], HIGH_PRIORITY_EMBED_COLOR);
+        assert_eq!(embed["url"], format!("{}/7", EXPLORER_URL));
+
+        let fields = embed["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), notification().fields().len() + 1);
+        assert_eq!(fields[0]["name"], "Stash");
+        assert_eq!(fields[0]["inline"], true);
+        assert_eq!(
+            fields.last().unwrap()["value"],
+            format!("[7]({}/7)", EXPLORER_URL)
+        );
+    }
+}
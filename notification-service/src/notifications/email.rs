@@ -0,0 +1,357 @@
+use std::{error::Error as _, time::Duration};
+
+use anyhow::{Context, Result};
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::{
+        authentication::Credentials,
+        client::{Tls, TlsParameters},
+    },
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use super::{ChannelHandle, NotificationMessage, NotificationSender, PermanentError, Priority};
+
+/// Prefix of the channel handles addressing mailboxes, e.g. `email:user@example.com`
+pub const EMAIL_PREFIX: &str = "email:";
+
+/// Reply codes of the SMTP server refusing the recipient mailbox, e.g. when it doesn't exist
+const RECIPIENT_REJECTED_CODES: [u16; 3] = [550, 551, 553];
+
+/// Enhanced status codes sent with the same reply codes when the server refuses the sender or the relay,
+/// which fails every email alike
+const SENDER_REJECTED_STATUSES: [&str; 3] = ["5.1.7", "5.1.8", "5.7."];
+
+/// Subject of the notification emails
+const SUBJECT: &str = "Aleph Zero event notification";
+
+/// Subject of the high priority notification emails
+const HIGH_PRIORITY_SUBJECT: &str = "[High priority] Aleph Zero event notification";
+
+/// Security of the connection to the SMTP server
+#[derive(Debug, Clone, Copy, Eq, PartialEq, clap::ValueEnum)]
+pub enum SmtpSecurity {
+    /// Plain text connection, e.g. to a local SMTP server
+    None,
+    /// Plain text connection upgraded with STARTTLS, usually on port 587
+    Starttls,
+    /// Connection encrypted with TLS from the start, usually on port 465
+    Tls,
+}
+
+/// Settings of the SMTP server the emails are sent through
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    /// Host name of the SMTP server
+    pub host: String,
+    /// Port of the SMTP server
+    pub port: u16,
+    /// Security of the connection
+    pub security: SmtpSecurity,
+    /// User name and password, if the server requires authentication
+    pub credentials: Option<(String, String)>,
+    /// Timeout of the SMTP commands
+    pub timeout: Duration,
+}
+
+/// A client sending notifications as emails through the SMTP server
+#[derive(Clone)]
+pub struct EmailSender {
+    /// SMTP transport keeping a pool of connections reused between emails
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+
+    /// Sender of the emails
+    from: Mailbox,
+}
+
+impl EmailSender {
+    /// Creates new instance of the email sender
+    pub fn new(settings: SmtpSettings, from: &str) -> Result<Self> {
+        let from = from
+            .parse::<Mailbox>()
+            .with_context(|| format!("Invalid sender address: {:?}", from))?;
+
+        let tls = match settings.security {
+            SmtpSecurity::None => Tls::None,
+            SmtpSecurity::Starttls => Tls::Required(
+                TlsParameters::new(settings.host.clone()).context("Invalid TLS settings")?,
+            ),
+            SmtpSecurity::Tls => Tls::Wrapper(
+                TlsParameters::new(settings.host.clone()).context("Invalid TLS settings")?,
+            ),
+        };
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+            .port(settings.port)
+            .tls(tls)
+            .timeout(Some(settings.timeout));
+        if let Some((username, password)) = settings.credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    fn parse_channel_handle(&self, channel_handle: ChannelHandle) -> Result<Mailbox> {
+        let address = channel_handle.0.strip_prefix(EMAIL_PREFIX).ok_or_else(|| {
+            PermanentError(format!("Unrecognized email handle: {:?}", channel_handle.0))
+        })?;
+
+        address.parse::<Mailbox>().map_err(|e| {
+            PermanentError(format!("Invalid email address {:?}: {}", address, e)).into()
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for EmailSender {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let to = self.parse_channel_handle(channel_handle)?;
+
+        log::info!("Sending email to {}: {}", to, msg);
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(match msg.priority() {
+                Priority::Normal => SUBJECT,
+                Priority::High => HIGH_PRIORITY_SUBJECT,
+            })
+            .multipart(MultiPart::alternative_plain_html(
+                msg.format(),
+                msg.format_html(),
+            ))
+            .context("Failed to build email")?;
+
+        let res = match self.transport.send(email).await {
+            // the server rejected the recipient, e.g. the mailbox does not exist
+            Err(err) if is_recipient_rejected(&err) => {
+                return Err(PermanentError(format!("SMTP server rejected email: {}", err)).into())
+            }
+            // other failures, e.g. rejected credentials or sender, are retried until the configuration is fixed
+            res => res.context("Failed to send email")?,
+        };
+
+        log::debug!("Response from SMTP server: {:?}", res);
+
+        Ok(())
+    }
+}
+
+/// Returns true if the server permanently rejected the recipient of the email, so it never can be delivered
+fn is_recipient_rejected(err: &lettre::transport::smtp::Error) -> bool {
+    let code = match err.status() {
+        Some(code) if err.is_permanent() => u16::from(code),
+        _ => return false,
+    };
+    let message = err.source().map(|e| e.to_string()).unwrap_or_default();
+
+    RECIPIENT_REJECTED_CODES.contains(&code)
+        && !SENDER_REJECTED_STATUSES
+            .iter()
+            .any(|status| message.trim_start().starts_with(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::notifications::test_server::notification;
+
+    const FROM: &str = "Notifications <notifications@example.com>";
+
+    /// Email received by the test SMTP server
+    #[derive(Debug, Clone, Default)]
+    struct Mail {
+        from: String,
+        to: Vec<String>,
+        data: String,
+    }
+
+    /// Starts plain text SMTP server on a random local port, answering senders and recipients with the given replies.
+    /// Returns the port and the emails received so far
+    async fn start_smtp_server(
+        mail_reply: &'static str,
+        rcpt_reply: &'static str,
+    ) -> (u16, Arc<Mutex<Vec<Mail>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mails = Arc::new(Mutex::new(Vec::new()));
+
+        let received = mails.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    let mut mail = Mail::default();
+                    writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let command = line.to_uppercase();
+                        let reply = if command.starts_with("MAIL FROM:") {
+                            mail.from = line[10..].trim().to_string();
+                            mail_reply
+                        } else if command.starts_with("RCPT TO:") {
+                            mail.to.push(line[8..].trim().to_string());
+                            rcpt_reply
+                        } else if command == "DATA" {
+                            writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                            while let Ok(Some(line)) = lines.next_line().await {
+                                if line == "." {
+                                    break;
+                                }
+                                mail.data.push_str(&line);
+                                mail.data.push('\n');
+                            }
+                            received.lock().unwrap().push(std::mem::take(&mut mail));
+                            "250 OK"
+                        } else if command == "QUIT" {
+                            writer.write_all(b"221 Bye\r\n").await.unwrap();
+                            break;
+                        } else {
+                            "250 localhost"
+                        };
+                        writer
+                            .write_all(format!("{}\r\n", reply).as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        (port, mails)
+    }
+
+    fn sender(port: u16) -> EmailSender {
+        EmailSender::new(
+            SmtpSettings {
+                host: "127.0.0.1".to_string(),
+                port,
+                security: SmtpSecurity::None,
+                credentials: None,
+                timeout: Duration::from_secs(5),
+            },
+            FROM,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_multipart_email() {
+        let (port, mails) = start_smtp_server("250 OK", "250 OK").await;
+
+        sender(port)
+            .send_notification(
+                notification(),
+                ChannelHandle(format!("{}user@example.com", EMAIL_PREFIX)),
+            )
+            .await
+            .unwrap();
+
+        let mails = mails.lock().unwrap().clone();
+        assert_eq!(mails.len(), 1);
+        let mail = &mails[0];
+        assert_eq!(mail.from, "<notifications@example.com>");
+        assert_eq!(mail.to, vec!["<user@example.com>".to_string()]);
+        assert!(mail
+            .data
+            .contains("From: Notifications <notifications@example.com>"));
+        assert!(mail.data.contains("To: user@example.com"));
+        assert!(mail
+            .data
+            .contains(&format!("Subject: {}", HIGH_PRIORITY_SUBJECT)));
+        assert!(mail.data.contains("Content-Type: multipart/alternative"));
+        assert!(mail
+            .data
+            .contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(mail.data.contains("Content-Type: text/html; charset=utf-8"));
+        // both parts carry the message, wrapped by the quoted-printable encoding
+        assert!(mail.data.contains("\nHIGH PRIORITY: stake of account"));
+        assert!(mail.data.contains("\n<p>HIGH PRIORITY: stake of account"));
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_on_rejected_recipient() {
+        let (port, mails) = start_smtp_server("250 OK", "550 5.1.1 No such user").await;
+
+        let err = sender(port)
+            .send_notification(
+                notification(),
+                ChannelHandle(format!("{}missing@example.com", EMAIL_PREFIX)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+        assert!(mails.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_on_temporary_rejection() {
+        let (port, _) = start_smtp_server("250 OK", "451 4.3.0 Try again later").await;
+
+        let err = sender(port)
+            .send_notification(
+                notification(),
+                ChannelHandle(format!("{}user@example.com", EMAIL_PREFIX)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_none());
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_when_sender_is_rejected() {
+        for (mail_reply, rcpt_reply) in [
+            ("550 5.1.8 Sender address rejected", "250 OK"),
+            ("530 5.7.0 Authentication required", "250 OK"),
+            ("250 OK", "550 5.7.1 Relaying denied"),
+        ] {
+            let (port, _) = start_smtp_server(mail_reply, rcpt_reply).await;
+
+            let err = sender(port)
+                .send_notification(
+                    notification(),
+                    ChannelHandle(format!("{}user@example.com", EMAIL_PREFIX)),
+                )
+                .await
+                .unwrap_err();
+
+            assert!(
+                err.downcast_ref::<PermanentError>().is_none(),
+                "{} / {}",
+                mail_reply,
+                rcpt_reply
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_on_invalid_handle() {
+        let err = sender(1)
+            .send_notification(
+                notification(),
+                ChannelHandle("email:not an address".to_string()),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::{header::HeaderMap, StatusCode, Url};
+use serde::Serialize;
+
+use super::{ChannelHandle, EventBlock, PermanentError, RetryAfter};
+
+/// Header with the number of requests left in the current rate limit window
+const RATE_LIMIT_REMAINING_HEADER: &str = "X-RateLimit-Remaining";
+
+/// Header with the number of seconds until the current rate limit window resets
+const RATE_LIMIT_RESET_AFTER_HEADER: &str = "X-RateLimit-Reset-After";
+
+/// Client posting json messages to incoming webhooks of chat platforms, e.g. Discord and Slack.
+/// Respects rate limits announced by the platform, separately for every webhook
+#[derive(Clone)]
+pub struct IncomingWebhook {
+    /// Name of the platform, used in logs and errors
+    platform: &'static str,
+
+    /// HTTP client reused for all the requests
+    client: reqwest::Client,
+
+    /// Moments until which the webhooks must not be called
+    blocked_until: Arc<Mutex<HashMap<Url, Instant>>>,
+}
+
+impl IncomingWebhook {
+    /// Creates new instance of the incoming webhook client
+    pub fn new(platform: &'static str, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            platform,
+            client,
+            blocked_until: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Extracts the webhook URL from the channel handle with a given prefix
+    pub fn parse_channel_handle(&self, channel_handle: ChannelHandle, prefix: &str) -> Result<Url> {
+        let url = channel_handle.0.strip_prefix(prefix).ok_or_else(|| {
+            PermanentError(format!(
+                "Unrecognized {} handle: {:?}",
+                self.platform, channel_handle.0
+            ))
+        })?;
+
+        match Url::parse(url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(url),
+            _ => Err(
+                PermanentError(format!("Invalid {} webhook URL: {:?}", self.platform, url)).into(),
+            ),
+        }
+    }
+
+    /// Posts the payload to the webhook. While the webhook is rate limited, returns the time to wait instead,
+    /// so the notification is retried by the outbox without holding back other webhooks
+    pub async fn post(&self, url: &Url, payload: &impl Serialize) -> Result<()> {
+        self.check_rate_limit(url)?;
+
+        let res = self
+            .client
+            .post(url.clone())
+            .json(payload)
+            .send()
+            .await
+            .with_context(|| format!("{} webhook request failed", self.platform))?;
+
+        let status = res.status();
+        if status.is_success() {
+            return self.update_rate_limit(url, res.headers());
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = header_seconds(res.headers(), reqwest::header::RETRY_AFTER)
+                .ok_or_else(|| anyhow!("{} webhook is rate limited", self.platform))?;
+            self.block(url, retry_after)?;
+            return Err(self.rate_limited(retry_after));
+        }
+        if status.is_server_error() {
+            bail!(
+                "{} webhook responded with status: {}",
+                self.platform,
+                status
+            );
+        }
+        // the platform rejected the message, e.g. the webhook was deleted
+        let body = res.text().await.unwrap_or_default();
+        Err(PermanentError(format!(
+            "{} webhook responded with status: {}, {}",
+            self.platform, status, body
+        ))
+        .into())
+    }
+
+    /// Fails with the time to wait if the webhook is still rate limited
+    fn check_rate_limit(&self, url: &Url) -> Result<()> {
+        let blocked_until = self
+            .blocked_until
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .get(url)
+            .copied();
+
+        match blocked_until {
+            Some(blocked_until) if blocked_until > Instant::now() => {
+                Err(self.rate_limited(blocked_until - Instant::now()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rate_limited(&self, delay: Duration) -> anyhow::Error {
+        RetryAfter {
+            delay,
+            reason: format!("{} webhook is rate limited", self.platform),
+        }
+        .into()
+    }
+
+    /// Blocks the webhook until the rate limit window resets if no more requests are left in it
+    fn update_rate_limit(&self, url: &Url, headers: &HeaderMap) -> Result<()> {
+        let remaining = headers
+            .get(RATE_LIMIT_REMAINING_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok());
+
+        match (
+            remaining,
+            header_seconds(headers, RATE_LIMIT_RESET_AFTER_HEADER),
+        ) {
+            (Some(0), Some(reset_after)) => self.block(url, reset_after),
+            _ => Ok(()),
+        }
+    }
+
+    fn block(&self, url: &Url, duration: Duration) -> Result<()> {
+        self.blocked_until
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .insert(url.clone(), Instant::now() + duration);
+        Ok(())
+    }
+}
+
+/// Reads the header value given in seconds, possibly fractional
+pub fn header_seconds(
+    headers: &HeaderMap,
+    name: impl reqwest::header::AsHeaderName,
+) -> Option<Duration> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<f64>().ok())
+        .and_then(|v| Duration::try_from_secs_f64(v).ok())
+}
+
+/// Returns link to the block in the block explorer
+pub fn block_link(explorer_url: &str, block: &EventBlock) -> String {
+    format!("{}/{}", explorer_url.trim_end_matches('/'), block.number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::test_server::{Response, TestServer};
+
+    fn webhook() -> IncomingWebhook {
+        IncomingWebhook::new("Test", Duration::from_secs(5)).unwrap()
+    }
+
+    fn url(server: &TestServer) -> Url {
+        Url::parse(&format!("{}/hook", server.url)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn asks_to_retry_until_rate_limit_resets() {
+        let server =
+            TestServer::start(vec![Response::status(429).with_header("Retry-After", "0.3")]).await;
+        let webhook = webhook();
+        let payload = serde_json::json!({ "text": "hello" });
+
+        let err = webhook.post(&url(&server), &payload).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RetryAfter>().unwrap().delay,
+            Duration::from_millis(300)
+        );
+
+        // the webhook is not called again until the limit resets
+        let err = webhook.post(&url(&server), &payload).await.unwrap_err();
+        assert!(err.downcast_ref::<RetryAfter>().unwrap().delay <= Duration::from_millis(300));
+        assert_eq!(server.requests().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        webhook.post(&url(&server), &payload).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn waits_for_reset_when_no_requests_are_left() {
+        let server = TestServer::start(vec![Response::status(200)
+            .with_header(RATE_LIMIT_REMAINING_HEADER, "0")
+            .with_header(RATE_LIMIT_RESET_AFTER_HEADER, "0.3")])
+        .await;
+        let webhook = webhook();
+        let payload = serde_json::json!({ "text": "hello" });
+
+        webhook.post(&url(&server), &payload).await.unwrap();
+
+        let err = webhook.post(&url(&server), &payload).await.unwrap_err();
+        assert!(err.downcast_ref::<RetryAfter>().is_some());
+        assert_eq!(server.requests().len(), 1);
+
+        // other webhooks are not limited
+        let other = Url::parse(&format!("{}/other", server.url)).unwrap();
+        webhook.post(&other, &payload).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        webhook.post(&url(&server), &payload).await.unwrap();
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_when_webhook_is_gone() {
+        let server = TestServer::start(vec![Response::status(404)]).await;
+
+        let err = webhook()
+            .post(&url(&server), &serde_json::json!({ "text": "hello" }))
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+    }
+}
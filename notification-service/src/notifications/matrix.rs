@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::{ChannelHandle, NotificationMessage, NotificationSender, PermanentError, RetryAfter};
+
+/// Prefix of the channel handles addressing Matrix rooms, e.g. `matrix:!roomid:example.com`
+pub const MATRIX_PREFIX: &str = "matrix:";
+
+/// Delay between attempts while the homeserver rejects the access token
+const UNAUTHORIZED_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Error returned by the Matrix homeserver
+#[derive(Debug, Default, Deserialize)]
+struct MatrixError {
+    /// Error code, e.g. `M_LIMIT_EXCEEDED`
+    #[serde(default)]
+    errcode: String,
+    /// Human readable error message
+    #[serde(default)]
+    error: String,
+    /// Time to wait before the next request when rate limited
+    retry_after_ms: Option<u64>,
+}
+
+/// A Matrix client sending notifications to rooms the user of the access token has joined
+#[derive(Clone)]
+pub struct MatrixSender {
+    /// HTTP client reused for all the requests
+    client: reqwest::Client,
+
+    /// Base URL of the homeserver client-server API, e.g. `https://matrix.org`
+    homeserver_url: Url,
+
+    /// Access token of the user sending the messages
+    access_token: String,
+}
+
+impl MatrixSender {
+    /// Creates new instance of the Matrix sender
+    pub fn new(homeserver_url: &str, access_token: String, timeout: Duration) -> Result<Self> {
+        let homeserver_url = Url::parse(homeserver_url)
+            .with_context(|| format!("Invalid homeserver URL: {:?}", homeserver_url))?;
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            client,
+            homeserver_url,
+            access_token,
+        })
+    }
+
+    fn parse_channel_handle(&self, channel_handle: ChannelHandle) -> Result<String> {
+        match channel_handle.0.strip_prefix(MATRIX_PREFIX) {
+            Some(room_id) if room_id.starts_with('!') && room_id.contains(':') => {
+                Ok(room_id.to_string())
+            }
+            _ => Err(PermanentError(format!(
+                "Unrecognized Matrix handle: {:?}",
+                channel_handle.0
+            ))
+            .into()),
+        }
+    }
+
+    /// Returns URL of the endpoint sending the message event to the room.
+    /// The transaction id makes retries of the same message idempotent
+    fn send_url(&self, room_id: &str, txn_id: &str) -> Result<Url> {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid homeserver URL: {}", self.homeserver_url))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                room_id,
+                "send",
+                "m.room.message",
+                txn_id,
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for MatrixSender {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let room_id = self.parse_channel_handle(channel_handle)?;
+
+        log::info!("Sending message to Matrix room {}: {}", room_id, msg);
+
+        let content = json!({
+            "msgtype": "m.text",
+            "body": msg.format(),
+            "format": "org.matrix.custom.html",
+            "formatted_body": msg.format_html(),
+        });
+        let txn_id = hex::encode(Sha256::digest(
+            serde_json::to_vec(&(&room_id, &msg)).context("Failed to serialize notification")?,
+        ));
+        let url = self.send_url(&room_id, &txn_id)?;
+
+        let res = self
+            .client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&content)
+            .send()
+            .await
+            .context("Matrix request failed")?;
+
+        let status = res.status();
+        if status.is_success() {
+            log::debug!("Response from Matrix: {:?}", res.text().await);
+            return Ok(());
+        }
+        let err = res.json::<MatrixError>().await.unwrap_or_default();
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => match err.retry_after_ms {
+                Some(retry_after_ms) => Err(RetryAfter {
+                    delay: Duration::from_millis(retry_after_ms),
+                    reason: format!("Matrix homeserver is rate limiting: {}", err.error),
+                }
+                .into()),
+                None => bail!("Matrix homeserver is rate limiting: {}", err.error),
+            },
+            // the access token is invalid for every room, e.g. expired or revoked. The notifications are kept
+            // in the outbox without counting the attempts until the service is restarted with a valid token
+            StatusCode::UNAUTHORIZED => {
+                log::error!(
+                    "Matrix homeserver rejected the access token, {}: {}. Replace --matrix-access-token",
+                    err.errcode,
+                    err.error
+                );
+                Err(RetryAfter {
+                    delay: UNAUTHORIZED_RETRY_DELAY,
+                    reason: format!(
+                        "Matrix homeserver rejected the access token, {}: {}",
+                        err.errcode, err.error
+                    ),
+                }
+                .into())
+            }
+            status if status.is_server_error() => {
+                bail!("Matrix homeserver responded with status: {}", status)
+            }
+            // the homeserver rejected the message, e.g. the user is not in the room
+            status => Err(PermanentError(format!(
+                "Matrix homeserver responded with status: {}, {}: {}",
+                status, err.errcode, err.error
+            ))
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{
+        test_server::{notification, Response, TestServer},
+        FormatToString,
+    };
+
+    const ACCESS_TOKEN: &str = "token";
+    const ROOM_ID: &str = "!room:example.com";
+
+    fn sender(server: &TestServer) -> MatrixSender {
+        MatrixSender::new(
+            &server.url,
+            ACCESS_TOKEN.to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    fn channel_handle() -> ChannelHandle {
+        ChannelHandle(format!("{}{}", MATRIX_PREFIX, ROOM_ID))
+    }
+
+    #[tokio::test]
+    async fn puts_room_message() {
+        let server = TestServer::start(vec![Response::json(200, r#"{"event_id":"$1"}"#)]).await;
+
+        sender(&server)
+            .send_notification(notification(), channel_handle())
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "PUT");
+        assert!(request.path.starts_with(&format!(
+            "/_matrix/client/v3/rooms/{}/send/m.room.message/",
+            ROOM_ID
+        )));
+        assert_eq!(
+            request.headers["authorization"],
+            format!("Bearer {}", ACCESS_TOKEN)
+        );
+
+        let content = request.json();
+        assert_eq!(content["msgtype"], "m.text");
+        assert_eq!(content["body"], notification().format());
+        assert_eq!(content["format"], "org.matrix.custom.html");
+        assert_eq!(content["formatted_body"], notification().format_html());
+    }
+
+    #[tokio::test]
+    async fn asks_to_retry_as_long_as_rate_limited() {
+        let server = TestServer::start(vec![Response::json(
+            429,
+            r#"{"errcode":"M_LIMIT_EXCEEDED","error":"Too many requests","retry_after_ms":100}"#,
+        )])
+        .await;
+        let sender = sender(&server);
+
+        let err = sender
+            .send_notification(notification(), channel_handle())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RetryAfter>().map(|err| err.delay),
+            Some(Duration::from_millis(100))
+        );
+
+        sender
+            .send_notification(notification(), channel_handle())
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        // the same transaction, so the homeserver doesn't duplicate the message
+        assert_eq!(requests[0].path, requests[1].path);
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_on_server_error() {
+        let server = TestServer::start(vec![Response::status(502)]).await;
+
+        let err = sender(&server)
+            .send_notification(notification(), channel_handle())
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_none());
+        assert!(err.downcast_ref::<RetryAfter>().is_none());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_when_access_token_is_rejected() {
+        for errcode in ["M_UNKNOWN_TOKEN", "M_MISSING_TOKEN"] {
+            let server = TestServer::start(vec![Response::json(
+                401,
+                &format!(
+                    r#"{{"errcode":"{}","error":"Invalid access token"}}"#,
+                    errcode
+                ),
+            )])
+            .await;
+
+            let err = sender(&server)
+                .send_notification(notification(), channel_handle())
+                .await
+                .unwrap_err();
+
+            assert_eq!(
+                err.downcast_ref::<RetryAfter>().unwrap().delay,
+                UNAUTHORIZED_RETRY_DELAY
+            );
+            assert!(err.to_string().contains(errcode));
+            assert_eq!(server.requests().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_when_rejected() {
+        for status in [403, 404] {
+            let server = TestServer::start(vec![Response::json(
+                status,
+                r#"{"errcode":"M_FORBIDDEN","error":"User is not in the room"}"#,
+            )])
+            .await;
+
+            let err = sender(&server)
+                .send_notification(notification(), channel_handle())
+                .await
+                .unwrap_err();
+
+            assert!(err.downcast_ref::<PermanentError>().is_some());
+            assert!(err.to_string().contains("M_FORBIDDEN"));
+            assert_eq!(server.requests().len(), 1);
+        }
+    }
+}
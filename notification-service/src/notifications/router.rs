@@ -0,0 +1,303 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use anyhow::Result;
+
+use super::{
+    discord::{DiscordSender, DISCORD_PREFIX},
+    email::{EmailSender, EMAIL_PREFIX},
+    matrix::{MatrixSender, MATRIX_PREFIX},
+    slack::{SlackSender, SLACK_PREFIX},
+    telegram::{TelegramBot, TELEGRAM_PREFIX},
+    webhook::{WebhookSender, WEBHOOK_PREFIX},
+    ChannelHandle, NotificationMessage, NotificationSender, PermanentError,
+};
+
+/// Prefixes of the Telegram handles used before the channels got their own scheme prefixes
+const LEGACY_TELEGRAM_PREFIXES: [&str; 2] = ["channel:", "chat_id:"];
+
+/// Notification channel chosen by the scheme prefix of the channel handle
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Channel {
+    Telegram,
+    Webhook,
+    Email,
+    Discord,
+    Slack,
+    Matrix,
+}
+
+/// Sends notifications through every configured channel,
+/// choosing the sender by the scheme prefix of the channel handle
+#[derive(Default)]
+pub struct ChannelRouter {
+    /// Sender of `telegram:` handles and Telegram handles without the prefix
+    telegram: Option<TelegramBot>,
+
+    /// Sender of `webhook:` handles
+    webhook: Option<WebhookSender>,
+
+    /// Sender of `email:` handles
+    email: Option<EmailSender>,
+
+    /// Sender of `discord:` handles
+    discord: Option<DiscordSender>,
+
+    /// Sender of `slack:` handles
+    slack: Option<SlackSender>,
+
+    /// Sender of `matrix:` handles
+    matrix: Option<MatrixSender>,
+
+    /// Channel handles already reported as rejected, so every one is logged once
+    rejected: Mutex<HashSet<String>>,
+}
+
+impl ChannelRouter {
+    /// Creates new instance of the router without any channel configured
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables `telegram:` channel handles
+    pub fn with_telegram(mut self, telegram: TelegramBot) -> Self {
+        self.telegram = Some(telegram);
+        self
+    }
+
+    /// Enables `webhook:` channel handles
+    pub fn with_webhook(mut self, webhook: WebhookSender) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
+    /// Enables `email:` channel handles
+    pub fn with_email(mut self, email: EmailSender) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Enables `discord:` channel handles
+    pub fn with_discord(mut self, discord: DiscordSender) -> Self {
+        self.discord = Some(discord);
+        self
+    }
+
+    /// Enables `slack:` channel handles
+    pub fn with_slack(mut self, slack: SlackSender) -> Self {
+        self.slack = Some(slack);
+        self
+    }
+
+    /// Enables `matrix:` channel handles
+    pub fn with_matrix(mut self, matrix: MatrixSender) -> Self {
+        self.matrix = Some(matrix);
+        self
+    }
+
+    /// Returns names of the configured channels
+    pub fn channels(&self) -> Vec<&'static str> {
+        [
+            (self.telegram.is_some(), "telegram"),
+            (self.webhook.is_some(), "webhook"),
+            (self.email.is_some(), "email"),
+            (self.discord.is_some(), "discord"),
+            (self.slack.is_some(), "slack"),
+            (self.matrix.is_some(), "matrix"),
+        ]
+        .into_iter()
+        .filter_map(|(configured, name)| configured.then_some(name))
+        .collect()
+    }
+
+    /// Chooses the channel by the scheme prefix of the handle
+    fn route(channel_handle: &ChannelHandle) -> Result<Channel, String> {
+        let handle = channel_handle.0.as_str();
+        let channel = [
+            (TELEGRAM_PREFIX, Channel::Telegram),
+            (WEBHOOK_PREFIX, Channel::Webhook),
+            (EMAIL_PREFIX, Channel::Email),
+            (DISCORD_PREFIX, Channel::Discord),
+            (SLACK_PREFIX, Channel::Slack),
+            (MATRIX_PREFIX, Channel::Matrix),
+        ]
+        .into_iter()
+        .find_map(|(prefix, channel)| handle.starts_with(prefix).then_some(channel));
+
+        match channel {
+            Some(channel) => Ok(channel),
+            None if LEGACY_TELEGRAM_PREFIXES
+                .iter()
+                .any(|prefix| handle.starts_with(prefix)) =>
+            {
+                Ok(Channel::Telegram)
+            }
+            None => Err(format!(
+                "Unknown channel scheme {:?} of the channel handle {:?}",
+                handle.split(':').next().unwrap_or_default(),
+                handle
+            )),
+        }
+    }
+
+    fn not_configured(&self, channel: Channel, channel_handle: &ChannelHandle) -> anyhow::Error {
+        let reason = format!(
+            "Channel {:?} of the channel handle {:?} is not configured",
+            channel, channel_handle.0
+        );
+        self.reject(channel_handle, reason)
+    }
+
+    /// Logs the rejected channel handle, once for every handle, and returns the permanent error
+    fn reject(&self, channel_handle: &ChannelHandle, reason: String) -> anyhow::Error {
+        if self.first_rejection(channel_handle) {
+            log::warn!("Rejecting notifications for channel handle: {}", reason);
+        }
+        PermanentError(reason).into()
+    }
+
+    /// Returns true if the channel handle is rejected for the first time
+    fn first_rejection(&self, channel_handle: &ChannelHandle) -> bool {
+        self.rejected
+            .lock()
+            .map(|mut rejected| rejected.insert(channel_handle.0.clone()))
+            .unwrap_or(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for ChannelRouter {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let channel = match Self::route(&channel_handle) {
+            Ok(channel) => channel,
+            Err(reason) => return Err(self.reject(&channel_handle, reason)),
+        };
+
+        match channel {
+            Channel::Telegram => match &self.telegram {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+            Channel::Webhook => match &self.webhook {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+            Channel::Email => match &self.email {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+            Channel::Discord => match &self.discord {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+            Channel::Slack => match &self.slack {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+            Channel::Matrix => match &self.matrix {
+                Some(sender) => sender.send_notification(msg, channel_handle).await,
+                None => Err(self.not_configured(channel, &channel_handle)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::notifications::test_server::{notification, TestServer};
+
+    fn handle(handle: &str) -> ChannelHandle {
+        ChannelHandle(handle.to_string())
+    }
+
+    fn router() -> ChannelRouter {
+        ChannelRouter::new()
+            .with_webhook(WebhookSender::new("secret".to_string(), Duration::from_secs(5)).unwrap())
+    }
+
+    #[test]
+    fn routes_by_scheme_prefix() {
+        for (channel_handle, channel) in [
+            ("telegram:chat_id:1", Channel::Telegram),
+            ("webhook:https://example.com", Channel::Webhook),
+            ("email:user@example.com", Channel::Email),
+            (
+                "discord:https://discord.com/api/webhooks/1/token",
+                Channel::Discord,
+            ),
+            (
+                "slack:https://hooks.slack.com/services/1/2/token",
+                Channel::Slack,
+            ),
+            ("matrix:!room:example.com", Channel::Matrix),
+        ] {
+            assert_eq!(ChannelRouter::route(&handle(channel_handle)), Ok(channel));
+        }
+    }
+
+    #[test]
+    fn routes_legacy_handles_to_telegram() {
+        for channel_handle in ["chat_id:1", "channel:@news"] {
+            assert_eq!(
+                ChannelRouter::route(&handle(channel_handle)),
+                Ok(Channel::Telegram)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_scheme() {
+        for channel_handle in ["sms:+48123456789", "1234", ""] {
+            assert!(ChannelRouter::route(&handle(channel_handle)).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_scheme_and_logs_it_once() {
+        let router = router();
+        let channel_handle = handle("sms:+48123456789");
+
+        let err = router
+            .send_notification(notification(), handle("sms:+48123456789"))
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+        assert!(err.to_string().contains("Unknown channel scheme \"sms\""));
+        // the rejection was already reported
+        assert!(!router.first_rejection(&channel_handle));
+        assert!(router.first_rejection(&handle("sms:+48987654321")));
+    }
+
+    #[tokio::test]
+    async fn rejects_channel_not_configured() {
+        let err = router()
+            .send_notification(notification(), handle("email:user@example.com"))
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+        assert!(err.to_string().contains("not configured"));
+    }
+
+    #[tokio::test]
+    async fn sends_through_configured_channel() {
+        let server = TestServer::start(vec![]).await;
+
+        router()
+            .send_notification(
+                notification(),
+                handle(&format!("webhook:{}/hook", server.url)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::{
+    incoming_webhook::{block_link, IncomingWebhook},
+    ChannelHandle, NotificationMessage, NotificationSender,
+};
+
+/// Prefix of the channel handles addressing Slack incoming webhooks,
+/// e.g. `slack:https://hooks.slack.com/services/<workspace>/<channel>/<token>`
+pub const SLACK_PREFIX: &str = "slack:";
+
+/// Maximum number of fields of a single section block accepted by Slack
+const MAX_SECTION_FIELDS: usize = 10;
+
+/// A client posting notifications as Block Kit messages to Slack incoming webhooks
+#[derive(Clone)]
+pub struct SlackSender {
+    /// Client of the incoming webhooks respecting Slack rate limits
+    webhook: IncomingWebhook,
+
+    /// Base URL of the block explorer, used to link the block of the event
+    explorer_url: String,
+}
+
+impl SlackSender {
+    /// Creates new instance of the Slack sender
+    pub fn new(explorer_url: String, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            webhook: IncomingWebhook::new("Slack", timeout)?,
+            explorer_url,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for SlackSender {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let url = self
+            .webhook
+            .parse_channel_handle(channel_handle, SLACK_PREFIX)?;
+
+        log::info!("Sending message to Slack: {}", msg);
+
+        let mut fields = msg
+            .fields()
+            .into_iter()
+            .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+            .collect::<Vec<_>>();
+        if let Some(block) = msg.block() {
+            fields.push(json!({
+                "type": "mrkdwn",
+                "text": format!("*Block*\n<{}|{}>", block_link(&self.explorer_url, &block), block.number),
+            }));
+        }
+
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": msg.title() },
+        })];
+        blocks.extend(sections(fields));
+
+        // the text is shown in the notifications of the Slack clients
+        let payload = json!({
+            "text": msg.format(),
+            "blocks": blocks,
+        });
+
+        self.webhook.post(&url, &payload).await
+    }
+}
+
+/// Splits the fields into as many section blocks as needed to keep within the Slack limit of fields per section
+fn sections(fields: Vec<Value>) -> Vec<Value> {
+    fields
+        .chunks(MAX_SECTION_FIELDS)
+        .map(|fields| json!({ "type": "section", "fields": fields }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{
+        test_server::{notification, TestServer},
+        FormatToFields, FormatToString,
+    };
+
+    const EXPLORER_URL: &str = "https://explorer.example.com/block";
+
+    #[tokio::test]
+    async fn posts_block_kit_message() {
+        let server = TestServer::start(vec![]).await;
+
+        SlackSender::new(EXPLORER_URL.to_string(), Duration::from_secs(5))
+            .unwrap()
+            .send_notification(
+                notification(),
+                ChannelHandle(format!("{}{}/hook", SLACK_PREFIX, server.url)),
+            )
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/hook");
+
+        let payload = requests[0].json();
+        assert_eq!(payload["text"], notification().format());
+        assert_eq!(payload["blocks"][0]["type"], "header");
+        assert_eq!(payload["blocks"][0]["text"]["text"], notification().title());
+        assert_eq!(payload["blocks"][1]["type"], "section");
+        let fields = payload["blocks"][1]["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), notification().fields().len() + 1);
+        assert_eq!(
+            fields.last().unwrap()["text"],
+            format!("*Block*\n<{}/7|7>", EXPLORER_URL)
+        );
+    }
+
+    #[test]
+    fn fields_are_split_into_sections() {
+        let fields = (0..23)
+            .map(|i| json!({ "type": "mrkdwn", "text": i.to_string() }))
+            .collect::<Vec<_>>();
+
+        let sections = sections(fields);
+
+        let lens = sections
+            .iter()
+            .map(|section| section["fields"].as_array().unwrap().len())
+            .collect::<Vec<_>>();
+        assert_eq!(lens, vec![10, 10, 3]);
+        assert_eq!(sections[2]["fields"][0]["text"], "20");
+        assert!(sections.iter().all(|section| section["type"] == "section"));
+    }
+}
@@ -1,9 +1,13 @@
 use core::fmt;
 
-use anyhow::{bail, Context, Result};
-use teloxide::{prelude::*, types::Recipient};
+use anyhow::{Context, Result};
+use teloxide::{prelude::*, types::Recipient, RequestError};
 
-use super::{ChannelHandle, NotificationMessage, NotificationSender};
+use super::{ChannelHandle, NotificationMessage, NotificationSender, PermanentError};
+
+/// Prefix of the channel handles addressing Telegram chats, e.g. `telegram:chat_id:222222222`.
+/// Handles without the prefix, e.g. `chat_id:222222222`, are accepted as well
+pub const TELEGRAM_PREFIX: &str = "telegram:";
 
 /// A Telegram client communicating with a bot
 #[derive(Clone, Eq, PartialEq)]
@@ -25,6 +29,11 @@ impl TelegramBot {
     }
 
     fn parse_channel_handle(&self, channel_handle: ChannelHandle) -> Result<Recipient> {
+        let channel_handle = match channel_handle.0.strip_prefix(TELEGRAM_PREFIX) {
+            Some(handle) => ChannelHandle(handle.to_string()),
+            None => channel_handle,
+        };
+
         if channel_handle.0.starts_with("channel:") {
             Ok(Recipient::ChannelUsername(
                 channel_handle.0.trim_start_matches("channel:").to_string(),
@@ -34,10 +43,20 @@ impl TelegramBot {
                 channel_handle
                     .0
                     .trim_start_matches("chat_id:")
-                    .parse::<i64>()?,
+                    .parse::<i64>()
+                    .map_err(|e| {
+                        PermanentError(format!(
+                            "Invalid Telegram chat id {:?}: {}",
+                            channel_handle.0, e
+                        ))
+                    })?,
             )))
         } else {
-            bail!("Unrecognized Telegram handle: {:?}", channel_handle.0)
+            Err(PermanentError(format!(
+                "Unrecognized Telegram handle: {:?}",
+                channel_handle.0
+            ))
+            .into())
         }
     }
 }
@@ -53,10 +72,16 @@ impl NotificationSender for TelegramBot {
 
         let bot = Bot::new(&self.bot_token);
 
-        let res = bot
+        let res = match bot
             .send_message(self.parse_channel_handle(channel_handle)?, msg.format())
             .await
-            .context("Failed to send message to Telegram bot")?;
+        {
+            // Telegram rejected the message, e.g. the chat does not exist or the bot is blocked
+            Err(RequestError::Api(err)) => {
+                return Err(PermanentError(format!("Telegram API error: {}", err)).into())
+            }
+            res => res.context("Failed to send message to Telegram bot")?,
+        };
 
         log::debug!("Response from Telegram: {:?}", res);
 
@@ -0,0 +1,195 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use aleph_client::{AccountId, BlockHash};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use super::{EventBlock, EventNotification, Notification, SlashedNotification};
+
+/// Returns notification about the event emitted in block 7 at index 2
+pub fn event_notification(notification: impl Into<Notification>) -> EventNotification {
+    EventNotification {
+        block: EventBlock {
+            number: 7,
+            hash: BlockHash::repeat_byte(1),
+        },
+        event_index: 2,
+        notification: notification.into(),
+    }
+}
+
+/// Returns high priority notification about 1.5 tokens slashed, sent by the tests of the senders
+pub fn notification() -> EventNotification {
+    event_notification(SlashedNotification {
+        stash_account: AccountId::from([1; 32]),
+        amount: 1_500_000_000_000,
+    })
+}
+
+/// Request received by the test server
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// HTTP method, e.g. `POST`
+    pub method: String,
+    /// Path and query of the request
+    pub path: String,
+    /// Headers by the lowercase name
+    pub headers: HashMap<String, String>,
+    /// Raw request body
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns body parsed as json
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is not json")
+    }
+}
+
+/// Scripted answer of the test server
+#[derive(Debug, Clone)]
+pub enum Response {
+    /// Responds with the status code, the headers and the json body
+    Status(u16, Vec<(String, String)>, String),
+    /// Doesn't respond for the given time, so the client times out
+    Stall(Duration),
+}
+
+impl Response {
+    /// Responds with the status code and an empty json object
+    pub fn status(code: u16) -> Self {
+        Self::json(code, "{}")
+    }
+
+    /// Responds with the status code and the json body
+    pub fn json(code: u16, body: &str) -> Self {
+        Response::Status(code, Vec::new(), body.to_string())
+    }
+
+    /// Adds the header to the response
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        if let Response::Status(_, headers, _) = &mut self {
+            headers.push((name.to_string(), value.to_string()));
+        }
+        self
+    }
+}
+
+/// Minimal HTTP server answering requests of the senders under test with scripted responses, in order.
+/// Answers `200 OK` once the responses are used up
+pub struct TestServer {
+    /// Base URL of the server, e.g. `http://127.0.0.1:1234`
+    pub url: String,
+
+    /// Requests received so far
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    /// Starts the server on a random local port
+    pub async fn start(responses: Vec<Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                let responses = responses.clone();
+                tokio::spawn(handle_connection(stream, received, responses));
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Returns requests received so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Records a single request and answers it with the next scripted response
+async fn handle_connection(
+    mut stream: TcpStream,
+    received: Arc<Mutex<Vec<Request>>>,
+    responses: Arc<Mutex<VecDeque<Response>>>,
+) -> Option<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|len| len.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = buf[header_end..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    received.lock().unwrap().push(Request {
+        method,
+        path,
+        headers,
+        body,
+    });
+    let response = responses
+        .lock()
+        .unwrap()
+        .pop_front()
+        .unwrap_or_else(|| Response::status(200));
+
+    match response {
+        Response::Status(code, headers, body) => {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                .collect::<String>();
+            let response = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                code,
+                body.len(),
+                headers,
+                body
+            );
+            stream.write_all(response.as_bytes()).await.ok()?;
+            stream.shutdown().await.ok()
+        }
+        Response::Stall(delay) => {
+            tokio::time::sleep(delay).await;
+            None
+        }
+    }
+}
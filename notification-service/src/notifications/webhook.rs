@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{StatusCode, Url};
+use serde::Serialize;
+use sha2::Sha256;
+
+use super::{
+    incoming_webhook::header_seconds, ChannelHandle, NotificationMessage, NotificationSender,
+    PermanentError, Priority, RetryAfter,
+};
+
+/// Prefix of the channel handles addressing webhooks, e.g. `webhook:https://example.com/notifications`
+pub const WEBHOOK_PREFIX: &str = "webhook:";
+
+/// Header carrying HMAC-SHA256 signature of the request body, formatted as `sha256=<hex digest>`
+pub const SIGNATURE_HEADER: &str = "X-Notification-Signature-256";
+
+/// Version of the json schema of the webhook payload
+const SCHEMA_VERSION: u32 = 1;
+
+/// Payload posted to the webhook
+#[derive(Serialize)]
+struct WebhookPayload<'a, T: Serialize> {
+    /// Version of the payload json schema
+    version: u32,
+    /// Notification details: event kind, block number and hash, accounts and amounts
+    #[serde(flatten)]
+    notification: &'a T,
+    /// Human readable notification message
+    message: String,
+    /// Urgency of the notification
+    priority: Priority,
+}
+
+/// A client posting notifications as json to HTTP webhooks
+#[derive(Clone)]
+pub struct WebhookSender {
+    /// HTTP client reused for all the requests
+    client: reqwest::Client,
+
+    /// Secret used to sign the request body
+    secret: String,
+}
+
+impl WebhookSender {
+    /// Creates new instance of the webhook sender
+    pub fn new(secret: String, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self { client, secret })
+    }
+
+    fn parse_channel_handle(&self, channel_handle: ChannelHandle) -> Result<Url> {
+        let url = channel_handle
+            .0
+            .strip_prefix(WEBHOOK_PREFIX)
+            .ok_or_else(|| {
+                PermanentError(format!(
+                    "Unrecognized webhook handle: {:?}",
+                    channel_handle.0
+                ))
+            })?;
+
+        match Url::parse(url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(url),
+            _ => Err(PermanentError(format!("Invalid webhook URL: {:?}", url)).into()),
+        }
+    }
+
+    /// Returns hex encoded HMAC-SHA256 signature of the body
+    fn sign(&self, body: &[u8]) -> Result<String> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .context("Invalid webhook secret")?;
+        mac.update(body);
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Posts the body to the webhook. Transient failures are returned as errors retried by the outbox
+    async fn post(&self, url: &Url, body: &[u8], signature: &str) -> Result<()> {
+        let res = self
+            .client
+            .post(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+            .body(body.to_vec())
+            .send()
+            .await
+            .context("Webhook request failed")?;
+
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            if let Some(delay) = header_seconds(res.headers(), reqwest::header::RETRY_AFTER) {
+                return Err(RetryAfter {
+                    delay,
+                    reason: "Webhook is rate limiting".to_string(),
+                }
+                .into());
+            }
+        }
+        if status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT
+        {
+            bail!("Webhook responded with status: {}", status);
+        }
+        // the webhook rejected the notification
+        Err(PermanentError(format!("Webhook responded with status: {}", status)).into())
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for WebhookSender {
+    async fn send_notification<T: NotificationMessage>(
+        &self,
+        msg: T,
+        channel_handle: ChannelHandle,
+    ) -> Result<()> {
+        let url = self.parse_channel_handle(channel_handle)?;
+
+        log::info!("Sending message to webhook {}: {}", url, msg);
+
+        let body = serde_json::to_vec(&WebhookPayload {
+            version: SCHEMA_VERSION,
+            notification: &msg,
+            message: msg.format(),
+            priority: msg.priority(),
+        })
+        .context("Failed to serialize webhook payload")?;
+        let signature = self.sign(&body)?;
+
+        self.post(&url, &body, &signature).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{
+        test_server::{notification, Response, TestServer},
+        FormatToString,
+    };
+
+    const SECRET: &str = "secret";
+
+    fn sender(timeout: Duration) -> WebhookSender {
+        WebhookSender::new(SECRET.to_string(), timeout).unwrap()
+    }
+
+    fn channel_handle(server: &TestServer) -> ChannelHandle {
+        ChannelHandle(format!("{}{}/hook", WEBHOOK_PREFIX, server.url))
+    }
+
+    #[tokio::test]
+    async fn posts_signed_json_payload() {
+        let server = TestServer::start(vec![]).await;
+
+        sender(Duration::from_secs(5))
+            .send_notification(notification(), channel_handle(&server))
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hook");
+        assert_eq!(request.headers["content-type"], "application/json");
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(&request.body);
+        assert_eq!(
+            request.headers[&SIGNATURE_HEADER.to_lowercase()],
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        );
+
+        let payload = request.json();
+        assert_eq!(payload["version"], SCHEMA_VERSION);
+        assert_eq!(payload["kind"], "slashed");
+        assert_eq!(payload["block"]["number"], 7);
+        assert_eq!(payload["event_index"], 2);
+        assert_eq!(payload["amount"], "1500000000000");
+        assert_eq!(payload["priority"], "high");
+        assert_eq!(payload["message"], notification().format());
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_on_server_error() {
+        let server = TestServer::start(vec![Response::status(503)]).await;
+
+        let err = sender(Duration::from_secs(5))
+            .send_notification(notification(), channel_handle(&server))
+            .await
+            .unwrap_err();
+
+        // retried by the outbox with backoff, not by the sender
+        assert!(err.downcast_ref::<PermanentError>().is_none());
+        assert!(err.downcast_ref::<RetryAfter>().is_none());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fails_transiently_on_timeout() {
+        let server = TestServer::start(vec![Response::Stall(Duration::from_secs(2))]).await;
+
+        let err = sender(Duration::from_millis(200))
+            .send_notification(notification(), channel_handle(&server))
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_none());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn asks_to_retry_after_rate_limit() {
+        let server =
+            TestServer::start(vec![Response::status(429).with_header("Retry-After", "2")]).await;
+
+        let err = sender(Duration::from_secs(5))
+            .send_notification(notification(), channel_handle(&server))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<RetryAfter>().map(|err| err.delay),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_on_client_error() {
+        let server = TestServer::start(vec![Response::status(400)]).await;
+
+        let err = sender(Duration::from_secs(5))
+            .send_notification(notification(), channel_handle(&server))
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<PermanentError>().is_some());
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,391 @@
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+
+use crate::{
+    notifications::{
+        ChannelHandle, EventNotification, NotificationSender, PermanentError, RetryAfter,
+    },
+    store::{OutboxEntry, Store},
+};
+
+/// Delay between checks for notifications due for delivery
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of notifications fetched from the outbox at once
+const BATCH_SIZE: u32 = 100;
+
+/// Maximum number of channel handles delivered to at once
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Delay before the first retry of a failed delivery
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Upper limit of the delay between delivery attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// How long delivered notifications are kept to recognize them when their block is replayed
+const DELIVERED_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Durable outbox of notifications. Every notification is persisted before it is sent.
+/// Failed deliveries are retried with exponential backoff and jitter, and moved to dead letters
+/// after the maximum number of attempts or on a permanent error.
+#[derive(Clone)]
+pub struct Outbox {
+    /// Terminates delivery loop
+    term: Arc<AtomicBool>,
+
+    /// Store persisting the outbox and dead letters
+    store: Arc<Store>,
+
+    /// Number of delivery attempts after which notification is moved to dead letters
+    max_attempts: u32,
+}
+
+impl Outbox {
+    /// Creates new instance of the outbox
+    pub fn new(term: Arc<AtomicBool>, store: Arc<Store>, max_attempts: u32) -> Self {
+        Self {
+            term,
+            store,
+            max_attempts,
+        }
+    }
+
+    /// Persists notification about the event for delivery to a given channel
+    pub fn enqueue(
+        &self,
+        channel_handle: &ChannelHandle,
+        notification: &EventNotification,
+    ) -> Result<()> {
+        let serialized =
+            serde_json::to_string(notification).context("Failed to serialize notification")?;
+        self.store.enqueue(
+            &notification.event_id(),
+            &channel_handle.0,
+            &serialized,
+            now(),
+        )
+    }
+
+    /// Delivers notifications from the outbox until terminated
+    pub async fn deliver(&self, notifier: &impl NotificationSender) -> Result<()> {
+        log::info!("Outbox delivery loop is live...");
+
+        while !self.term.load(std::sync::atomic::Ordering::Relaxed) {
+            let entries = self.store.due_outbox_entries(now(), BATCH_SIZE)?;
+            if entries.is_empty() {
+                self.store
+                    .prune_delivered(now() - DELIVERED_RETENTION.as_millis() as i64)?;
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            self.deliver_batch(entries, notifier).await?;
+        }
+
+        bail!("Outbox delivery loop terminated")
+    }
+
+    /// Delivers notifications for different channel handles concurrently, so a slow, failing or rate limited
+    /// channel doesn't hold back the others. Notifications for the same channel handle are delivered one by one,
+    /// in order. Once one of them fails, the rest are postponed until its next attempt
+    async fn deliver_batch(
+        &self,
+        entries: Vec<OutboxEntry>,
+        notifier: &impl NotificationSender,
+    ) -> Result<()> {
+        let mut channels = HashMap::<String, Vec<OutboxEntry>>::new();
+        for entry in entries {
+            channels
+                .entry(entry.channel_handle.clone())
+                .or_default()
+                .push(entry);
+        }
+
+        futures::stream::iter(channels.into_values())
+            .map(|entries| self.deliver_channel(entries, notifier))
+            .buffer_unordered(MAX_CONCURRENT_DELIVERIES)
+            .try_collect::<()>()
+            .await
+    }
+
+    async fn deliver_channel(
+        &self,
+        entries: Vec<OutboxEntry>,
+        notifier: &impl NotificationSender,
+    ) -> Result<()> {
+        let mut entries = entries.into_iter();
+        while let Some(entry) = entries.next() {
+            if let Some(next_attempt_at) = self.deliver_entry(entry, notifier).await? {
+                for entry in entries {
+                    self.store.reschedule_outbox_entry(
+                        entry.id,
+                        entry.attempts,
+                        next_attempt_at,
+                        "Postponed after a failed delivery to the same channel",
+                    )?;
+                }
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delivers the notification. Returns the time of the next attempt if the notification was rescheduled
+    async fn deliver_entry(
+        &self,
+        entry: OutboxEntry,
+        notifier: &impl NotificationSender,
+    ) -> Result<Option<i64>> {
+        let attempts = entry.attempts + 1;
+        let res = match serde_json::from_str::<EventNotification>(&entry.notification) {
+            Ok(notification) => {
+                notifier
+                    .send_notification(notification, ChannelHandle(entry.channel_handle.clone()))
+                    .await
+            }
+            Err(err) => Err(PermanentError(format!("Invalid notification: {}", err)).into()),
+        };
+
+        let err = match res {
+            Ok(_) => {
+                self.store.mark_delivered(entry.id, now())?;
+                return Ok(None);
+            }
+            Err(err) => err,
+        };
+
+        if let Some(retry_after) = err.downcast_ref::<RetryAfter>() {
+            // waiting for the channel is not a failed attempt
+            log::info!(
+                "Delivery of notification {} for {} postponed by {:?}: {}",
+                entry.id,
+                entry.channel_handle,
+                retry_after.delay,
+                retry_after.reason
+            );
+            let next_attempt_at = now() + retry_after.delay.as_millis() as i64;
+            self.store.reschedule_outbox_entry(
+                entry.id,
+                entry.attempts,
+                next_attempt_at,
+                &retry_after.to_string(),
+            )?;
+            return Ok(Some(next_attempt_at));
+        }
+
+        if err.downcast_ref::<PermanentError>().is_some() || attempts >= self.max_attempts {
+            log::error!(
+                "Notification {} for {} moved to dead letters after {} attempts, error: {:#}",
+                entry.id,
+                entry.channel_handle,
+                attempts,
+                err
+            );
+            self.store
+                .move_to_dead_letters(entry.id, attempts, &format!("{:#}", err), now())?;
+            return Ok(None);
+        }
+
+        let backoff = backoff(attempts);
+        log::warn!(
+            "Failed to deliver notification {} for {} (attempt {}), retrying in {:?}, error: {:#}",
+            entry.id,
+            entry.channel_handle,
+            attempts,
+            backoff,
+            err
+        );
+        let next_attempt_at = now() + backoff.as_millis() as i64;
+        self.store.reschedule_outbox_entry(
+            entry.id,
+            attempts,
+            next_attempt_at,
+            &format!("{:#}", err),
+        )?;
+        Ok(Some(next_attempt_at))
+    }
+}
+
+/// Returns the delay before the next delivery attempt. The delay doubles with every failed attempt
+/// and is randomized between its half and full value, so retries of many notifications are spread over time
+fn backoff(attempts: u32) -> Duration {
+    let backoff = INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF);
+    let half = backoff / 2;
+    half + Duration::from_millis(rand::thread_rng().gen_range(0..=half.as_millis() as u64))
+}
+
+/// Returns current time in milliseconds since the unix epoch
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Mutex};
+
+    use aleph_client::AccountId;
+
+    use super::*;
+    use crate::notifications::{
+        test_server::event_notification, NotificationMessage, ValidatorsChangedNotification,
+    };
+
+    fn outbox() -> Outbox {
+        let store = Store::open(Path::new(":memory:")).unwrap();
+        Outbox::new(Arc::new(AtomicBool::new(false)), Arc::new(store), 3)
+    }
+
+    fn validators_changed(validator: [u8; 32]) -> EventNotification {
+        event_notification(ValidatorsChangedNotification {
+            validator_account: AccountId::from(validator),
+            included: true,
+        })
+    }
+
+    #[test]
+    fn enqueue_keeps_notifications_about_every_account_of_event() {
+        let outbox = outbox();
+        let channel_handle = ChannelHandle("webhook:https://example.com".to_string());
+
+        // one event involving two accounts watched by the same subscription
+        outbox
+            .enqueue(&channel_handle, &validators_changed([1; 32]))
+            .unwrap();
+        outbox
+            .enqueue(&channel_handle, &validators_changed([2; 32]))
+            .unwrap();
+        // replayed block
+        outbox
+            .enqueue(&channel_handle, &validators_changed([1; 32]))
+            .unwrap();
+
+        let entries = outbox.store.due_outbox_entries(now(), 10).unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    /// Records delivered notifications. Delivers to `slow:` handles with a delay
+    /// and asks to retry notifications for `limited:` handles later
+    #[derive(Default)]
+    struct RecordingSender {
+        delivered: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NotificationSender for RecordingSender {
+        async fn send_notification<T: NotificationMessage>(
+            &self,
+            _msg: T,
+            channel_handle: ChannelHandle,
+        ) -> Result<()> {
+            if channel_handle.0.starts_with("slow:") {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            if channel_handle.0.starts_with("limited:") {
+                return Err(RetryAfter {
+                    delay: Duration::from_secs(60),
+                    reason: "rate limited".to_string(),
+                }
+                .into());
+            }
+            self.delivered.lock().unwrap().push(channel_handle.0);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_channel_does_not_hold_back_others() {
+        let outbox = outbox();
+        for (channel_handle, validator) in [
+            ("slow:1", [1; 32]),
+            ("slow:2", [2; 32]),
+            ("fast:1", [3; 32]),
+        ] {
+            outbox
+                .enqueue(
+                    &ChannelHandle(channel_handle.to_string()),
+                    &validators_changed(validator),
+                )
+                .unwrap();
+        }
+        let notifier = RecordingSender::default();
+
+        let entries = outbox.store.due_outbox_entries(now(), 10).unwrap();
+        outbox.deliver_batch(entries, &notifier).await.unwrap();
+
+        // both slow handles are delivered at once, each of them after the fast one
+        let delivered = notifier.delivered.lock().unwrap().clone();
+        assert_eq!(delivered.len(), 3);
+        assert_eq!(delivered[0], "fast:1");
+        assert!(outbox
+            .store
+            .due_outbox_entries(now(), 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_channel_postpones_its_notifications_without_counting_attempts() {
+        let outbox = outbox();
+        for (channel_handle, validator) in [
+            ("limited:1", [1; 32]),
+            ("limited:1", [2; 32]),
+            ("fast:1", [3; 32]),
+        ] {
+            outbox
+                .enqueue(
+                    &ChannelHandle(channel_handle.to_string()),
+                    &validators_changed(validator),
+                )
+                .unwrap();
+        }
+        let notifier = RecordingSender::default();
+
+        let entries = outbox.store.due_outbox_entries(now(), 10).unwrap();
+        outbox.deliver_batch(entries, &notifier).await.unwrap();
+
+        assert_eq!(*notifier.delivered.lock().unwrap(), vec!["fast:1"]);
+        assert!(outbox
+            .store
+            .due_outbox_entries(now(), 10)
+            .unwrap()
+            .is_empty());
+
+        // only the first notification was sent, both wait for the rate limit to reset
+        let entries = outbox.store.due_outbox_entries(now() + 61_000, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries
+            .iter()
+            .all(|entry| entry.channel_handle == "limited:1" && entry.attempts == 0));
+    }
+
+    #[test]
+    fn backoff_stays_between_half_and_full_capped_delay() {
+        for attempts in 1..=20 {
+            let capped = (INITIAL_BACKOFF * 2u32.pow(attempts - 1)).min(MAX_BACKOFF);
+            for _ in 0..100 {
+                let backoff = backoff(attempts);
+                assert!(
+                    backoff >= capped / 2,
+                    "{:?} for {} attempts",
+                    backoff,
+                    attempts
+                );
+                assert!(backoff <= capped, "{:?} for {} attempts", backoff, attempts);
+            }
+        }
+        assert!(backoff(1) <= INITIAL_BACKOFF);
+        assert!(backoff(u32::MAX) >= MAX_BACKOFF / 2);
+    }
+}
@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use aleph_client::{
+    contract::{event::translate_events, ContractInstance, ConvertibleValue},
+    AccountId, AlephConfig, Balance, Connection,
+};
+use anyhow::{anyhow, Context, Result};
+use contract_transcode::Value;
+use subxt::events::EventDetails;
+
+use crate::{
+    contract_events::WatchedContract,
+    notifications::{
+        Notification, Psp22ApprovalNotification, Psp22TransferNotification, TransferDirection,
+    },
+};
+
+/// Message of the PSP22 metadata extension returning the token symbol
+const TOKEN_SYMBOL_MESSAGE: &str = "PSP22Metadata::token_symbol";
+
+/// Message of the PSP22 metadata extension returning the token decimals
+const TOKEN_DECIMALS_MESSAGE: &str = "PSP22Metadata::token_decimals";
+
+/// Name of the standard PSP22 transfer event
+const TRANSFER_EVENT: &str = "Transfer";
+
+/// Name of the standard PSP22 approval event
+const APPROVAL_EVENT: &str = "Approval";
+
+/// PSP22 token contract together with its metadata used to format amounts
+struct Psp22Token {
+    /// Client of the token contract, built from its metadata
+    contract: ContractInstance,
+
+    /// Token details shown in the notifications
+    info: TokenInfo,
+}
+
+/// Details of the token shown in the notifications
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct TokenInfo {
+    /// On chain address of the token contract
+    address: AccountId,
+
+    /// Symbol of the token, e.g. `USDT`
+    symbol: String,
+
+    /// Number of decimals of the token amounts
+    decimals: u8,
+}
+
+/// Decodes the standard `Transfer` and `Approval` events of the configured PSP22 token contracts
+pub struct Psp22Tokens {
+    tokens: Vec<Psp22Token>,
+}
+
+impl std::fmt::Debug for Psp22Tokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.tokens
+                    .iter()
+                    .map(|t| format!("{} ({})", t.info.symbol, t.info.address)),
+            )
+            .finish()
+    }
+}
+
+impl Psp22Tokens {
+    /// Loads metadata of every token contract and reads the token symbol and decimals through contract queries
+    pub async fn new(conn: &Connection, watched_tokens: &[WatchedContract]) -> Result<Self> {
+        let mut tokens = Vec::with_capacity(watched_tokens.len());
+        for token in watched_tokens {
+            let metadata_path = token
+                .metadata
+                .to_str()
+                .context("Token's metadata path is not valid unicode")?;
+            let contract = ContractInstance::new(token.address.clone(), metadata_path)
+                .with_context(|| {
+                    format!("Failed to load metadata of the token {}", token.address)
+                })?;
+
+            let symbol: Option<String> = contract
+                .contract_read0(conn, TOKEN_SYMBOL_MESSAGE)
+                .await
+                .with_context(|| format!("Failed to read symbol of the token {}", token.address))?;
+            let decimals: u128 = contract
+                .contract_read0(conn, TOKEN_DECIMALS_MESSAGE)
+                .await
+                .with_context(|| {
+                    format!("Failed to read decimals of the token {}", token.address)
+                })?;
+
+            tokens.push(Psp22Token {
+                contract,
+                info: TokenInfo {
+                    address: token.address.clone(),
+                    symbol: symbol.unwrap_or_else(|| token.address.to_string()),
+                    decimals: u8::try_from(decimals).with_context(|| {
+                        format!("Invalid decimals of the token {}", token.address)
+                    })?,
+                },
+            });
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Returns true if no token is watched
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Decodes the event if it is a `Transfer` or `Approval` of one of the tokens, and returns notifications about it:
+    /// incoming for the receiver and outgoing for the sender of a transfer, and for the owner of the approved tokens,
+    /// together with the transferred or approved amount. Decoding failures are logged and skipped
+    pub fn notifications(
+        &self,
+        event: &EventDetails<AlephConfig>,
+    ) -> Vec<(AccountId, Balance, Notification)> {
+        let contracts = self.tokens.iter().map(|t| &t.contract).collect::<Vec<_>>();
+        let mut notifications = vec![];
+
+        for contract_event in translate_events(
+            std::iter::once(Ok::<_, subxt::Error>(event.clone())),
+            &contracts,
+            None,
+        ) {
+            let contract_event = match contract_event {
+                Ok(contract_event) => contract_event,
+                Err(err) => {
+                    log::warn!("Failed to decode PSP22 token event: {:?}", err);
+                    continue;
+                }
+            };
+            let token = match self
+                .tokens
+                .iter()
+                .find(|t| t.contract.address() == &contract_event.contract)
+            {
+                Some(token) => token,
+                None => continue,
+            };
+
+            match event_notifications(
+                &token.info,
+                contract_event.name.as_deref(),
+                &contract_event.data,
+            ) {
+                Ok(event_notifications) => notifications.extend(event_notifications),
+                Err(err) => log::warn!(
+                    "Failed to decode {:?} event of the token {}: {}",
+                    contract_event.name,
+                    token.info.symbol,
+                    err
+                ),
+            }
+        }
+        notifications
+    }
+}
+
+/// Returns notifications about the decoded event of the token if it is the standard `Transfer` or `Approval`.
+/// Other events of the token are ignored
+fn event_notifications(
+    token: &TokenInfo,
+    name: Option<&str>,
+    data: &HashMap<String, Value>,
+) -> Result<Vec<(AccountId, Balance, Notification)>> {
+    let mut notifications = vec![];
+    match name {
+        Some(TRANSFER_EVENT) => transfer_notifications(token, data, &mut notifications)?,
+        Some(APPROVAL_EVENT) => approval_notifications(token, data, &mut notifications)?,
+        _ => (),
+    }
+    Ok(notifications)
+}
+
+fn transfer_notifications(
+    token: &TokenInfo,
+    data: &HashMap<String, Value>,
+    notifications: &mut Vec<(AccountId, Balance, Notification)>,
+) -> Result<()> {
+    let from: Option<AccountId> = decode_field(data, "from")?;
+    let to: Option<AccountId> = decode_field(data, "to")?;
+    let amount: Balance = decode_field(data, "value")?;
+
+    let notification = |direction| Psp22TransferNotification {
+        token: token.address.clone(),
+        symbol: token.symbol.clone(),
+        decimals: token.decimals,
+        from_account: from.clone(),
+        to_account: to.clone(),
+        amount,
+        direction,
+    };
+    if let Some(to) = &to {
+        notifications.push((
+            to.clone(),
+            amount,
+            notification(TransferDirection::Incoming).into(),
+        ));
+    }
+    if let Some(from) = &from {
+        notifications.push((
+            from.clone(),
+            amount,
+            notification(TransferDirection::Outgoing).into(),
+        ));
+    }
+    Ok(())
+}
+
+fn approval_notifications(
+    token: &TokenInfo,
+    data: &HashMap<String, Value>,
+    notifications: &mut Vec<(AccountId, Balance, Notification)>,
+) -> Result<()> {
+    let owner: AccountId = decode_field(data, "owner")?;
+    let spender: AccountId = decode_field(data, "spender")?;
+    let amount: Balance = decode_field(data, "value")?;
+
+    notifications.push((
+        owner.clone(),
+        amount,
+        Psp22ApprovalNotification {
+            token: token.address.clone(),
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+            owner_account: owner,
+            spender_account: spender,
+            amount,
+        }
+        .into(),
+    ));
+    Ok(())
+}
+
+fn decode_field<T: TryFrom<ConvertibleValue, Error = anyhow::Error>>(
+    data: &HashMap<String, Value>,
+    name: &str,
+) -> Result<T> {
+    let value = data
+        .get(name)
+        .ok_or_else(|| anyhow!("missing field `{}`", name))?;
+    ConvertibleValue(value.clone())
+        .try_into()
+        .with_context(|| format!("failed to decode field `{}`", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use contract_transcode::Tuple;
+
+    use super::*;
+
+    const TOKEN: &str = "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM";
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn account(address: &str) -> AccountId {
+        AccountId::from_str(address).unwrap()
+    }
+
+    fn token() -> TokenInfo {
+        TokenInfo {
+            address: account(TOKEN),
+            symbol: "USDT".to_string(),
+            decimals: 6,
+        }
+    }
+
+    fn some(address: &str) -> Value {
+        Value::Tuple(Tuple::new(
+            Some("Some"),
+            vec![Value::Literal(address.to_string())],
+        ))
+    }
+
+    fn none() -> Value {
+        Value::Tuple(Tuple::new(Some("None"), vec![]))
+    }
+
+    fn data(fields: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_transfer_for_both_sides() {
+        let data = data(vec![
+            ("from", some(ALICE)),
+            ("to", some(BOB)),
+            ("value", Value::UInt(1_500_000)),
+        ]);
+
+        let notifications = event_notifications(&token(), Some(TRANSFER_EVENT), &data).unwrap();
+
+        let transfer = |direction| {
+            Notification::from(Psp22TransferNotification {
+                token: account(TOKEN),
+                symbol: "USDT".to_string(),
+                decimals: 6,
+                from_account: Some(account(ALICE)),
+                to_account: Some(account(BOB)),
+                amount: 1_500_000,
+                direction,
+            })
+        };
+        assert_eq!(
+            notifications,
+            vec![
+                (
+                    account(BOB),
+                    1_500_000,
+                    transfer(TransferDirection::Incoming)
+                ),
+                (
+                    account(ALICE),
+                    1_500_000,
+                    transfer(TransferDirection::Outgoing)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_mint_for_receiver_only() {
+        let data = data(vec![
+            ("from", none()),
+            ("to", some(BOB)),
+            ("value", Value::UInt(1)),
+        ]);
+
+        let notifications = event_notifications(&token(), Some(TRANSFER_EVENT), &data).unwrap();
+
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].0, account(BOB));
+    }
+
+    #[test]
+    fn decodes_approval_for_owner() {
+        let data = data(vec![
+            ("owner", Value::Literal(ALICE.to_string())),
+            ("spender", Value::Literal(BOB.to_string())),
+            ("value", Value::UInt(7)),
+        ]);
+
+        let notifications = event_notifications(&token(), Some(APPROVAL_EVENT), &data).unwrap();
+
+        assert_eq!(
+            notifications,
+            vec![(
+                account(ALICE),
+                7,
+                Psp22ApprovalNotification {
+                    token: account(TOKEN),
+                    symbol: "USDT".to_string(),
+                    decimals: 6,
+                    owner_account: account(ALICE),
+                    spender_account: account(BOB),
+                    amount: 7,
+                }
+                .into()
+            )]
+        );
+    }
+
+    #[test]
+    fn ignores_other_events() {
+        let data = data(vec![
+            ("from", some(ALICE)),
+            ("to", some(BOB)),
+            ("value", Value::UInt(1)),
+        ]);
+
+        for name in [Some("BatchTransfer"), Some("transfer"), None] {
+            assert!(event_notifications(&token(), name, &data)
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn fails_on_missing_field() {
+        let data = data(vec![("from", some(ALICE)), ("to", some(BOB))]);
+
+        assert!(event_notifications(&token(), Some(TRANSFER_EVENT), &data).is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use std::{collections::HashSet, path::Path, sync::Mutex, time::Duration};
+
+use aleph_client::{AccountId, BlockHash, BlockNumber};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::OptionalExtension;
+
+/// Name of the cursor tracking blocks processed by the events handler
+pub const EVENTS_CURSOR: &str = "events";
+
+/// Name of the cursor tracking blocks processed by the Subscriptions smart contract client
+pub const SUBSCRIPTIONS_CURSOR: &str = "subscriptions";
+
+/// Represents the last fully processed finalized block
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Cursor {
+    /// Number of the processed block
+    pub block_number: BlockNumber,
+
+    /// Hash of the processed block
+    pub block_hash: BlockHash,
+}
+
+/// Notification waiting in the outbox for delivery
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OutboxEntry {
+    /// Unique identifier of the entry
+    pub id: i64,
+
+    /// Handle of the channel the notification is sent to
+    pub channel_handle: String,
+
+    /// Notification serialized to json
+    pub notification: String,
+
+    /// Number of failed delivery attempts
+    pub attempts: u32,
+}
+
+/// Notification that could not be delivered
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DeadLetter {
+    /// Unique identifier of the dead letter, the same as of the outbox entry
+    pub id: i64,
+
+    /// Handle of the channel the notification was sent to
+    pub channel_handle: String,
+
+    /// Notification serialized to json
+    pub notification: String,
+
+    /// Number of failed delivery attempts
+    pub attempts: u32,
+
+    /// Error returned by the last delivery attempt
+    pub last_error: String,
+
+    /// When the notification was moved to dead letters, in milliseconds since the unix epoch
+    pub failed_at: i64,
+}
+
+/// Local, file based store of the notification service state
+pub struct Store {
+    /// Connection to the SQLite database
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl std::fmt::Debug for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Store")
+    }
+}
+
+impl Store {
+    /// Opens the store at a given path. Creates the database file and its tables if they don't exist
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = rusqlite::Connection::open(path)
+            .with_context(|| format!("Failed to open the store at {:?}", path))?;
+        // the store can be accessed concurrently, e.g. when dead letters are re-driven
+        conn.busy_timeout(Duration::from_secs(5))
+            .context("Failed to configure the store")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cursors (
+                name TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                block_hash BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                event_id TEXT NOT NULL,
+                channel_handle TEXT NOT NULL,
+                notification TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at INTEGER NOT NULL,
+                last_error TEXT,
+                delivered_at INTEGER,
+                UNIQUE (event_id, channel_handle)
+            );
+            CREATE TABLE IF NOT EXISTS dead_letters (
+                id INTEGER PRIMARY KEY,
+                event_id TEXT NOT NULL,
+                channel_handle TEXT NOT NULL,
+                notification TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT NOT NULL,
+                failed_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS validators (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                accounts BLOB NOT NULL
+            );",
+        )
+        .context("Failed to create the store tables")?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns the last fully processed block for a given cursor name
+    pub fn cursor(&self, name: &str) -> Result<Option<Cursor>> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let res = conn
+            .query_row(
+                "SELECT block_number, block_hash FROM cursors WHERE name = ?1",
+                [name],
+                |row| Ok((row.get::<_, BlockNumber>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()
+            .context("Failed to read the cursor")?;
+
+        Ok(res.map(|(block_number, block_hash)| Cursor {
+            block_number,
+            block_hash: BlockHash::from_slice(&block_hash),
+        }))
+    }
+
+    /// Persists the last fully processed block for a given cursor name
+    pub fn save_cursor(&self, name: &str, cursor: &Cursor) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        conn.execute(
+            "INSERT INTO cursors (name, block_number, block_hash) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET block_number = ?2, block_hash = ?3",
+            rusqlite::params![name, cursor.block_number, cursor.block_hash.as_bytes()],
+        )
+        .context("Failed to save the cursor")?;
+
+        Ok(())
+    }
+
+    /// Returns the validators announced by the last processed `ChangeValidators` event.
+    /// Unknown until the first such event is processed
+    pub fn validators(&self) -> Result<Option<HashSet<AccountId>>> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let accounts = conn
+            .query_row("SELECT accounts FROM validators WHERE id = 0", [], |row| {
+                row.get::<_, Vec<u8>>(0)
+            })
+            .optional()
+            .context("Failed to read the validators")?;
+
+        accounts
+            .map(|accounts| {
+                accounts
+                    .chunks(32)
+                    .map(|account| {
+                        <[u8; 32]>::try_from(account)
+                            .map(AccountId::from)
+                            .context("Invalid validator account in the store")
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Persists the validators announced by the last processed `ChangeValidators` event
+    pub fn save_validators(&self, validators: &HashSet<AccountId>) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let accounts = validators
+            .iter()
+            .flat_map(|account| AsRef::<[u8]>::as_ref(account).to_vec())
+            .collect::<Vec<_>>();
+        conn.execute(
+            "INSERT INTO validators (id, accounts) VALUES (0, ?1)
+             ON CONFLICT(id) DO UPDATE SET accounts = ?1",
+            [accounts],
+        )
+        .context("Failed to save the validators")?;
+
+        Ok(())
+    }
+
+    /// Adds notification to the outbox. A notification already added for the same event and channel,
+    /// even if already delivered, is ignored, so replaying a block doesn't duplicate notifications
+    pub fn enqueue(
+        &self,
+        event_id: &str,
+        channel_handle: &str,
+        notification: &str,
+        now: i64,
+    ) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO outbox (event_id, channel_handle, notification, next_attempt_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![event_id, channel_handle, notification, now],
+        )
+        .context("Failed to add notification to the outbox")?;
+
+        Ok(())
+    }
+
+    /// Returns outbox entries due for the delivery attempt, the oldest first
+    pub fn due_outbox_entries(&self, now: i64, limit: u32) -> Result<Vec<OutboxEntry>> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, channel_handle, notification, attempts FROM outbox
+             WHERE delivered_at IS NULL AND next_attempt_at <= ?1 ORDER BY next_attempt_at, id LIMIT ?2",
+        )?;
+        let entries = stmt
+            .query_map(rusqlite::params![now, limit], |row| {
+                Ok(OutboxEntry {
+                    id: row.get(0)?,
+                    channel_handle: row.get(1)?,
+                    notification: row.get(2)?,
+                    attempts: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read the outbox")?;
+
+        Ok(entries)
+    }
+
+    /// Marks notification in the outbox as delivered
+    pub fn mark_delivered(&self, id: i64, now: i64) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        conn.execute(
+            "UPDATE outbox SET delivered_at = ?2 WHERE id = ?1",
+            rusqlite::params![id, now],
+        )
+        .context("Failed to mark notification as delivered")?;
+
+        Ok(())
+    }
+
+    /// Removes notifications delivered before a given time from the outbox
+    pub fn prune_delivered(&self, before: i64) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        conn.execute("DELETE FROM outbox WHERE delivered_at < ?1", [before])
+            .context("Failed to prune delivered notifications")?;
+
+        Ok(())
+    }
+
+    /// Records failed delivery attempt and schedules the next one
+    pub fn reschedule_outbox_entry(
+        &self,
+        id: i64,
+        attempts: u32,
+        next_attempt_at: i64,
+        error: &str,
+    ) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        conn.execute(
+            "UPDATE outbox SET attempts = ?2, next_attempt_at = ?3, last_error = ?4 WHERE id = ?1",
+            rusqlite::params![id, attempts, next_attempt_at, error],
+        )
+        .context("Failed to reschedule notification in the outbox")?;
+
+        Ok(())
+    }
+
+    /// Moves notification from the outbox to dead letters
+    pub fn move_to_dead_letters(
+        &self,
+        id: i64,
+        attempts: u32,
+        error: &str,
+        now: i64,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO dead_letters (id, event_id, channel_handle, notification, attempts, last_error, failed_at)
+             SELECT id, event_id, channel_handle, notification, ?2, ?3, ?4 FROM outbox WHERE id = ?1",
+            rusqlite::params![id, attempts, error, now],
+        )?;
+        tx.execute("DELETE FROM outbox WHERE id = ?1", [id])?;
+        tx.commit()
+            .context("Failed to move notification to dead letters")?;
+
+        Ok(())
+    }
+
+    /// Returns all dead letters, the oldest first
+    pub fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, channel_handle, notification, attempts, last_error, failed_at
+             FROM dead_letters ORDER BY failed_at, id",
+        )?;
+        let dead_letters = stmt
+            .query_map([], |row| {
+                Ok(DeadLetter {
+                    id: row.get(0)?,
+                    channel_handle: row.get(1)?,
+                    notification: row.get(2)?,
+                    attempts: row.get(3)?,
+                    last_error: row.get(4)?,
+                    failed_at: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read dead letters")?;
+
+        Ok(dead_letters)
+    }
+
+    /// Moves dead letters back to the outbox for immediate delivery with the attempts counter reset.
+    /// Moves all dead letters if the id is not given. Returns the number of moved dead letters
+    pub fn redrive_dead_letters(&self, id: Option<i64>, now: i64) -> Result<usize> {
+        let mut conn = self.conn.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+        let tx = conn.transaction()?;
+        // the notification could have been added to the outbox again in the meantime
+        let moved = tx.execute(
+            "INSERT OR IGNORE INTO outbox (id, event_id, channel_handle, notification, attempts, next_attempt_at)
+             SELECT id, event_id, channel_handle, notification, 0, ?2 FROM dead_letters
+             WHERE ?1 IS NULL OR id = ?1",
+            rusqlite::params![id, now],
+        )?;
+        tx.execute(
+            "DELETE FROM dead_letters WHERE ?1 IS NULL OR id = ?1",
+            rusqlite::params![id],
+        )?;
+        tx.commit()
+            .context("Failed to move dead letters to the outbox")?;
+
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HANDLE: &str = "chat_id:1";
+
+    fn store() -> Store {
+        Store::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn due_ids(store: &Store, now: i64) -> Vec<i64> {
+        store
+            .due_outbox_entries(now, 10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect()
+    }
+
+    #[test]
+    fn cursor_is_saved() {
+        let store = store();
+        assert_eq!(store.cursor(EVENTS_CURSOR).unwrap(), None);
+
+        for block_number in [1, 2] {
+            let cursor = Cursor {
+                block_number,
+                block_hash: BlockHash::repeat_byte(block_number as u8),
+            };
+            store.save_cursor(EVENTS_CURSOR, &cursor).unwrap();
+            assert_eq!(store.cursor(EVENTS_CURSOR).unwrap(), Some(cursor));
+        }
+        assert_eq!(store.cursor(SUBSCRIPTIONS_CURSOR).unwrap(), None);
+    }
+
+    #[test]
+    fn validators_are_saved() {
+        let store = store();
+        assert_eq!(store.validators().unwrap(), None);
+
+        let validators = HashSet::from([AccountId::from([1; 32]), AccountId::from([2; 32])]);
+        store.save_validators(&validators).unwrap();
+        assert_eq!(store.validators().unwrap(), Some(validators));
+
+        store.save_validators(&HashSet::new()).unwrap();
+        assert_eq!(store.validators().unwrap(), Some(HashSet::new()));
+    }
+
+    #[test]
+    fn enqueue_ignores_duplicates() {
+        let store = store();
+        store.enqueue("7:2", HANDLE, "first", 0).unwrap();
+        store.enqueue("7:2", HANDLE, "second", 0).unwrap();
+        store.enqueue("7:2", "chat_id:2", "other", 0).unwrap();
+
+        let entries = store.due_outbox_entries(0, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].notification, "first");
+        assert_eq!(entries[0].attempts, 0);
+
+        // delivered notifications are remembered too
+        store.mark_delivered(entries[0].id, 1).unwrap();
+        store.enqueue("7:2", HANDLE, "replayed", 2).unwrap();
+        assert_eq!(due_ids(&store, 2), vec![entries[1].id]);
+    }
+
+    #[test]
+    fn due_outbox_entries_are_ordered_by_next_attempt() {
+        let store = store();
+        store.enqueue("1:0", HANDLE, "a", 10).unwrap();
+        store.enqueue("2:0", HANDLE, "b", 5).unwrap();
+        store.enqueue("3:0", HANDLE, "c", 20).unwrap();
+        store.enqueue("4:0", HANDLE, "d", 10).unwrap();
+        let ids = due_ids(&store, 20);
+        let (a, b, c, d) = (ids[1], ids[0], ids[3], ids[2]);
+        assert_eq!(store.due_outbox_entries(20, 2).unwrap().len(), 2);
+        assert_eq!(due_ids(&store, 15), vec![b, a, d]);
+
+        store.reschedule_outbox_entry(a, 1, 30, "failed").unwrap();
+        assert_eq!(due_ids(&store, 25), vec![b, d, c]);
+        assert_eq!(due_ids(&store, 30), vec![b, d, c, a]);
+        let entry = store
+            .due_outbox_entries(30, 10)
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.id == a)
+            .unwrap();
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.notification, "a");
+    }
+
+    #[test]
+    fn dead_letters_are_redriven() {
+        let store = store();
+        store.enqueue("1:0", HANDLE, "a", 0).unwrap();
+        store.enqueue("2:0", HANDLE, "b", 0).unwrap();
+        store.enqueue("3:0", HANDLE, "c", 0).unwrap();
+        let ids = due_ids(&store, 0);
+        for id in &ids {
+            store.move_to_dead_letters(*id, 5, "rejected", 100).unwrap();
+        }
+        assert!(due_ids(&store, 1000).is_empty());
+
+        let dead_letters = store.dead_letters().unwrap();
+        assert_eq!(dead_letters.len(), 3);
+        assert_eq!(
+            dead_letters[0],
+            DeadLetter {
+                id: ids[0],
+                channel_handle: HANDLE.to_string(),
+                notification: "a".to_string(),
+                attempts: 5,
+                last_error: "rejected".to_string(),
+                failed_at: 100,
+            }
+        );
+
+        // a single dead letter is moved back with the attempts counter reset
+        assert_eq!(store.redrive_dead_letters(Some(ids[0]), 200).unwrap(), 1);
+        let entries = store.due_outbox_entries(200, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, ids[0]);
+        assert_eq!(entries[0].attempts, 0);
+        assert!(due_ids(&store, 199).is_empty());
+        assert_eq!(store.dead_letters().unwrap().len(), 2);
+
+        // the notification added to the outbox again in the meantime is not duplicated
+        store.enqueue("2:0", HANDLE, "b", 300).unwrap();
+        assert_eq!(store.redrive_dead_letters(None, 300).unwrap(), 1);
+        assert_eq!(store.due_outbox_entries(300, 10).unwrap().len(), 3);
+        assert!(store.dead_letters().unwrap().is_empty());
+    }
+
+    #[test]
+    fn prune_delivered_removes_old_notifications() {
+        let store = store();
+        store.enqueue("1:0", HANDLE, "a", 0).unwrap();
+        store.enqueue("2:0", HANDLE, "b", 0).unwrap();
+        let ids = due_ids(&store, 0);
+        store.mark_delivered(ids[0], 100).unwrap();
+
+        store.prune_delivered(100).unwrap();
+        store.enqueue("1:0", HANDLE, "a", 150).unwrap();
+        assert_eq!(due_ids(&store, 150), vec![ids[1]]);
+
+        // pending notifications are never pruned
+        store.prune_delivered(200).unwrap();
+        assert_eq!(due_ids(&store, 200), vec![ids[1]]);
+        // the pruned notification is not remembered anymore
+        store.enqueue("1:0", HANDLE, "a", 200).unwrap();
+        assert_eq!(due_ids(&store, 200).len(), 2);
+    }
+}
@@ -1,15 +1,24 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::Path,
     sync::{atomic::AtomicBool, Arc, Mutex},
 };
 
 use aleph_client::{
     contract::{event::translate_events, ContractInstance, ConvertibleValue},
-    AccountId, Connection,
+    AccountId, Balance, Connection,
 };
 use anyhow::{anyhow, bail, Context, Result};
-use futures::StreamExt;
+
+use crate::{
+    blocks::FinalizedBlocks,
+    events::EventKind,
+    notifications::{
+        ChannelHandle, EventBlock, EventNotification, SubscriptionExtendedNotification,
+    },
+    outbox::Outbox,
+    store::{Cursor, Store, SUBSCRIPTIONS_CURSOR},
+};
 
 /// Represents subscription for on-chain account
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -17,8 +26,27 @@ pub struct Subscription {
     /// Who registerred new subscription. Events published for this account will result in notifications
     pub for_account: AccountId,
 
-    /// A handle (e.g. chat_id) associated with the user's subscription
-    pub channel_handle: String,
+    /// Handles (e.g. chat_id) associated with the user's subscription. Notifications are sent to each of them
+    pub channel_handles: Vec<String>,
+
+    /// Kinds of events the user is notified about
+    pub event_kinds: Vec<EventKind>,
+
+    /// Minimum amounts per event kind, events with lower amounts are not notified
+    pub min_amounts: HashMap<EventKind, Balance>,
+
+    /// Accounts watched besides the subscribed account. Events published for them also result in notifications
+    pub watched_accounts: Vec<AccountId>,
+
+    /// Whether the subscription is paused. Paused subscriptions do not result in notifications
+    pub paused: bool,
+}
+
+impl Subscription {
+    /// Returns every account watched by the subscription, starting with the subscribed account
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountId> {
+        std::iter::once(&self.for_account).chain(self.watched_accounts.iter())
+    }
 }
 
 impl TryFrom<ConvertibleValue> for Subscription {
@@ -31,7 +59,11 @@ impl TryFrom<ConvertibleValue> for Subscription {
         };
 
         let for_account: AccountId;
-        let channel_handle: String;
+        let channel_handles: Vec<String>;
+        let event_kinds: Vec<EventKind>;
+        let min_amounts: HashMap<EventKind, Balance>;
+        let watched_accounts: Vec<AccountId>;
+        let paused: bool;
 
         match map.ident() {
             Some(x) if x == "ActiveSubscriptionAttr" => {
@@ -43,16 +75,49 @@ impl TryFrom<ConvertibleValue> for Subscription {
                     ),
                 }
 
-                match map.get_by_str("external_channel_handle") {
-                    Some(x) => channel_handle = ConvertibleValue(x.clone()).try_into()?,
+                match map.get_by_str("external_channel_handles") {
+                    Some(x) => channel_handles = ConvertibleValue(x.clone()).try_into()?,
+                    _ => bail!(
+                        "Failed parsing `external_channel_handles`. Expected `Vec<Vec<u8>>` but got: {:?}",
+                        x
+                    ),
+                }
+
+                match map.get_by_str("event_kinds") {
+                    Some(x) => event_kinds = decode_event_kinds(x)?,
+                    _ => bail!(
+                        "Failed parsing `event_kinds`. Expected `Vec<EventKind>` but got: {:?}",
+                        x
+                    ),
+                }
+
+                match map.get_by_str("min_amounts") {
+                    Some(x) => min_amounts = decode_min_amounts(x)?,
                     _ => bail!(
-                        "Failed parsing `external_channel_handle`. Expected `Vec<u8>` but got: {:?}",
+                        "Failed parsing `min_amounts`. Expected `Vec<(EventKind, Balance)>` but got: {:?}",
                         x
                     ),
                 }
+
+                match map.get_by_str("watched_accounts") {
+                    Some(x) => watched_accounts = ConvertibleValue(x.clone()).try_into()?,
+                    _ => bail!(
+                        "Failed parsing `watched_accounts`. Expected `Vec<AccountId>` but got: {:?}",
+                        x
+                    ),
+                }
+
+                match map.get_by_str("paused") {
+                    Some(x) => paused = ConvertibleValue(x.clone()).try_into()?,
+                    _ => bail!("Failed parsing `paused`. Expected `bool` but got: {:?}", x),
+                }
                 Ok(Subscription {
                     for_account,
-                    channel_handle,
+                    channel_handles,
+                    event_kinds,
+                    min_amounts,
+                    watched_accounts,
+                    paused,
                 })
             }
             _ => bail!(
@@ -63,6 +128,101 @@ impl TryFrom<ConvertibleValue> for Subscription {
     }
 }
 
+/// Active subscriptions indexed by the subscribed account and by every account they watch
+#[derive(Debug, Default)]
+pub struct ActiveSubscriptions {
+    /// Subscriptions by the subscribed account
+    subscriptions: HashMap<AccountId, Subscription>,
+
+    /// Subscribed accounts by the watched account. A single account may be watched by several subscriptions
+    watchers: HashMap<AccountId, HashSet<AccountId>>,
+}
+
+impl ActiveSubscriptions {
+    /// Adds the subscription, replacing the previous one of the same subscribed account
+    pub fn insert(&mut self, subscription: Subscription) {
+        self.remove(&subscription.for_account);
+        for account in subscription.accounts() {
+            self.watchers
+                .entry(account.clone())
+                .or_default()
+                .insert(subscription.for_account.clone());
+        }
+        self.subscriptions
+            .insert(subscription.for_account.clone(), subscription);
+    }
+
+    /// Removes the subscription of the subscribed account
+    pub fn remove(&mut self, for_account: &AccountId) -> Option<Subscription> {
+        let subscription = self.subscriptions.remove(for_account)?;
+        for account in subscription.accounts() {
+            self.unwatch(account, for_account);
+        }
+        Some(subscription)
+    }
+
+    /// Returns the subscription of the subscribed account
+    pub fn get(&self, for_account: &AccountId) -> Option<&Subscription> {
+        self.subscriptions.get(for_account)
+    }
+
+    /// Returns the subscription of the subscribed account for update.
+    /// Watched accounts must be changed with `add_watched_account` and `remove_watched_account` to keep the index consistent
+    pub fn get_mut(&mut self, for_account: &AccountId) -> Option<&mut Subscription> {
+        self.subscriptions.get_mut(for_account)
+    }
+
+    /// Adds the account watched by the subscription. Returns `false` if the subscription is unknown
+    pub fn add_watched_account(&mut self, for_account: &AccountId, account: AccountId) -> bool {
+        let subscription = match self.subscriptions.get_mut(for_account) {
+            Some(subscription) => subscription,
+            None => return false,
+        };
+        if !subscription.watched_accounts.contains(&account) {
+            subscription.watched_accounts.push(account.clone());
+        }
+        self.watchers
+            .entry(account)
+            .or_default()
+            .insert(for_account.clone());
+        true
+    }
+
+    /// Removes the account watched by the subscription. Returns `false` if the subscription is unknown
+    pub fn remove_watched_account(&mut self, for_account: &AccountId, account: &AccountId) -> bool {
+        let subscription = match self.subscriptions.get_mut(for_account) {
+            Some(subscription) => subscription,
+            None => return false,
+        };
+        subscription.watched_accounts.retain(|a| a != account);
+        self.unwatch(account, for_account);
+        true
+    }
+
+    /// Returns every subscription watching the account, including the subscription of the account itself
+    pub fn watching<'a>(&'a self, account: &AccountId) -> impl Iterator<Item = &'a Subscription> {
+        self.watchers
+            .get(account)
+            .into_iter()
+            .flatten()
+            .filter_map(|for_account| self.subscriptions.get(for_account))
+    }
+
+    /// Returns every active subscription
+    pub fn values(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions.values()
+    }
+
+    fn unwatch(&mut self, account: &AccountId, for_account: &AccountId) {
+        if let Some(watchers) = self.watchers.get_mut(account) {
+            watchers.remove(for_account);
+            if watchers.is_empty() {
+                self.watchers.remove(account);
+            }
+        }
+    }
+}
+
 /// Represents a middleware communicating with Subscriptions smart contract
 pub struct Subscriptions {
     /// Terminates event handling loop
@@ -74,8 +234,14 @@ pub struct Subscriptions {
     /// A connection to the aleph zero node
     connection: Connection,
 
-    /// List of active subscriptions, each represented as an on-chain account id
-    pub active_subscriptions: Arc<Mutex<HashMap<AccountId, Subscription>>>,
+    /// Active subscriptions, indexed by the subscribed and the watched accounts
+    pub active_subscriptions: Arc<Mutex<ActiveSubscriptions>>,
+
+    /// Store persisting the last processed block
+    store: Arc<Store>,
+
+    /// Webservice endpoint addresses of the Aleph Zero nodes, used in turn on reconnection
+    node_addresses: Vec<String>,
 }
 
 impl std::fmt::Debug for Subscriptions {
@@ -89,185 +255,575 @@ impl Subscriptions {
     pub fn new(
         term: Arc<AtomicBool>,
         sc_address: AccountId,
-        node_address: &str,
+        node_addresses: &[String],
         sc_metadata_path: &Path,
+        store: Arc<Store>,
     ) -> Result<Self> {
         let sc_matadata_path = sc_metadata_path
             .to_str()
             .context("Smart contract's metadata not set")?;
 
+        let node_address = node_addresses.first().context("Node address not set")?;
         let conn = futures::executor::block_on(Connection::new(node_address));
 
         Ok(Self {
             term,
             contract: ContractInstance::new(sc_address, sc_matadata_path)?,
             connection: conn,
-            active_subscriptions: Arc::new(Mutex::new(HashMap::default())),
+            active_subscriptions: Arc::new(Mutex::new(ActiveSubscriptions::default())),
+            store,
+            node_addresses: node_addresses.to_vec(),
         })
     }
 
-    /// Retrieves list of active subscriptions for which notifications should be sent
+    /// Returns the connection to the aleph zero node used by this client
+    pub fn connection(&self) -> Connection {
+        self.connection.clone()
+    }
+
+    /// Retrieves list of active subscriptions for which notifications should be sent.
+    /// Smart contract events are handled from the block the list was read at, so the events already reflected
+    /// in the list, e.g. a cancellation, are not applied on top of it again
     pub async fn init_subscriptions(&mut self) -> Result<()> {
-        let res: Result<Result<Vec<Subscription>>> = self
-            .contract
-            .contract_read0(&self.connection, "get_active_subscriptions")
-            .await;
+        let (retrieved_active_subscriptions, cursor) = loop {
+            let before = self.best_block().await?;
+            let res: Result<Result<Vec<Subscription>>> = self
+                .contract
+                .contract_read0(&self.connection, "get_active_subscriptions")
+                .await;
+            let retrieved_active_subscriptions = res??;
 
-        let retrieved_active_subscriptions = res??;
+            // the list must be read at a known block
+            if self.best_block().await? == before {
+                break (retrieved_active_subscriptions, before);
+            }
+            log::debug!("New block imported while reading active subscriptions, reading again");
+        };
 
         let mut active_subscriptions = self
             .active_subscriptions
             .lock()
             .map_err(|e| anyhow!(e.to_string()))?;
 
-        for subs in retrieved_active_subscriptions.iter() {
-            active_subscriptions.insert(subs.for_account.clone(), subs.clone());
+        for subs in retrieved_active_subscriptions.into_iter() {
+            active_subscriptions.insert(subs);
         }
 
-        Ok(())
+        log::info!(
+            "Active subscriptions read at block: {} ({:?})",
+            cursor.block_number,
+            cursor.block_hash
+        );
+        self.store.save_cursor(SUBSCRIPTIONS_CURSOR, &cursor)
     }
 
-    /// Listens for smart contract events: NewSubscription, CancelledSubscription, CancelledSubscriptions
-    /// For each event either add new subscription or remove active subscriptions.
-    pub async fn handle_events(&mut self) -> Result<()> {
-        let mut block_sub = self
-            .connection
-            .as_client()
-            .blocks()
-            .subscribe_finalized()
+    /// Returns the best block of the connected node
+    async fn best_block(&self) -> Result<Cursor> {
+        let rpc = self.connection.as_client().rpc();
+        let block_hash = rpc
+            .block_hash(None)
+            .await
+            .context("Failed to get hash of the best block")?
+            .ok_or_else(|| anyhow!("Best block not found"))?;
+        let header = rpc
+            .header(Some(block_hash))
             .await
-            .context("failed to subscribe for subscriptions smart contract events")?;
+            .context("Failed to get header of the best block")?
+            .ok_or_else(|| anyhow!("Best block header not found"))?;
 
-        log::info!("aleph-client for subscriptions smart contract is waiting for events...");
+        Ok(Cursor {
+            block_number: header.number,
+            block_hash,
+        })
+    }
 
-        while let Some(Ok(block)) = block_sub.next().await {
-            if self.term.load(std::sync::atomic::Ordering::Relaxed) {
-                bail!("Subscriptions smart contract event loop terminated")
-            }
+    /// Listens for smart contract events: NewSubscription, SubscriptionExtended, SubscriptionPaused, SubscriptionResumed,
+    /// ChannelHandleUpdated, EventKindsUpdated, MinAmountsUpdated, WatchedAccountAdded, WatchedAccountRemoved,
+    /// CancelledSubscription, CancelledSubscriptions
+    /// For each event either add new subscription, update or remove active subscriptions.
+    /// Extensions of subscriptions are confirmed to the subscribers through the outbox.
+    pub async fn handle_events(&mut self, outbox: &Outbox) -> Result<()> {
+        let cursor = self.store.cursor(SUBSCRIPTIONS_CURSOR)?;
+        let mut blocks = FinalizedBlocks::new(
+            Arc::clone(&self.term),
+            self.connection.as_client().clone(),
+            self.node_addresses.clone(),
+            cursor,
+        );
 
+        log::info!("aleph-client for subscriptions smart contract is waiting for events...");
+
+        while let Some(block) = blocks.next().await {
             let events = match block.events().await {
                 Ok(events) => events,
-                _ => continue,
-            };
-
-            for event in translate_events(
-                events.iter(),
-                &[&self.contract],
-                Some(aleph_client::contract::event::BlockDetails {
-                    block_number: block.number(),
-                    block_hash: block.hash(),
-                }),
-            ) {
-                if event.is_err() {
+                Err(err) => {
                     log::error!(
-                        "Error receiving Subscriptions contract event: {:?}",
-                        event.err()
+                        "Failed to obtain events of the block {}: {}",
+                        block.number(),
+                        err
                     );
+                    blocks.replay(&block);
                     continue;
                 }
-                let event = event.unwrap();
-                log::info!("Received smart contract event: {:?}", event);
-                match &event.name {
-                    Some(n) if n == "NewSubscription" => {
-                        let for_account =
-                            match self.decode_account_id(event.data.get("for_account")) {
-                                Ok(v) => v,
-                                Err(err) => {
-                                    log::error!(
+            };
+
+            let event_block = EventBlock {
+                number: block.number(),
+                hash: block.hash(),
+            };
+            for event in events.iter() {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::error!("Error receiving Subscriptions contract event: {:?}", err);
+                        continue;
+                    }
+                };
+                // contract events are translated one by one to know their index in the block
+                let event_index = event.index();
+                for event in translate_events(
+                    std::iter::once(Ok(event)),
+                    &[&self.contract],
+                    Some(aleph_client::contract::event::BlockDetails {
+                        block_number: block.number(),
+                        block_hash: block.hash(),
+                    }),
+                ) {
+                    if event.is_err() {
+                        log::error!(
+                            "Error receiving Subscriptions contract event: {:?}",
+                            event.err()
+                        );
+                        continue;
+                    }
+                    let event = event.unwrap();
+                    log::info!("Received smart contract event: {:?}", event);
+                    match &event.name {
+                        Some(n) if n == "NewSubscription" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
                                         "AddSubscription event failed to decode for_account: {}",
                                         err
                                     );
+                                        continue;
+                                    }
+                                };
+
+                            let channel_handles = match self
+                                .decode_strings(event.data.get("external_channel_handles"))
+                            {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                    "AddSubscription event failed to decode channel_handles: {}",
+                                    err
+                                );
                                     continue;
                                 }
                             };
 
-                        let channel_handle =
-                            match self.decode_string(event.data.get("external_channel_handle")) {
+                            let event_kinds =
+                                match self.decode_event_kinds(event.data.get("event_kinds")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "AddSubscription event failed to decode event_kinds: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+
+                            let payer = match self.decode_account_id(event.data.get("payer")) {
                                 Ok(v) => v,
                                 Err(err) => {
                                     log::error!(
-                                        "AddSubscription event failed to decode channel_handle: {}",
+                                        "AddSubscription event failed to decode payer: {}",
                                         err
                                     );
                                     continue;
                                 }
                             };
 
-                        let mut active_subscriptions = match self.active_subscriptions.lock() {
-                            Ok(v) => v,
-                            Err(err) => {
-                                log::error!("Unable to lock active_subscriptions: {:?}", err);
-                                continue;
-                            }
-                        };
-                        active_subscriptions.insert(
-                            for_account.clone(),
-                            Subscription {
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            active_subscriptions.insert(Subscription {
                                 for_account: for_account.clone(),
-                                channel_handle,
-                            },
+                                channel_handles,
+                                event_kinds,
+                                min_amounts: HashMap::default(),
+                                watched_accounts: Vec::new(),
+                                paused: false,
+                            });
+
+                            log::info!(
+                                "New subscription for account: {:?}, paid by: {:?}",
+                                for_account,
+                                payer
+                            );
+                        }
+                        Some(n) if n == "SubscriptionExtended" => {
+                            let for_account = match self
+                                .decode_account_id(event.data.get("for_account"))
+                            {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                        "SubscriptionExtended event failed to decode for_account: {}",
+                                        err
+                                    );
+                                    continue;
+                                }
+                            };
+                            let intervals = match self.decode_u32(event.data.get("intervals")) {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                        "SubscriptionExtended event failed to decode intervals: {}",
+                                        err
+                                    );
+                                    continue;
+                                }
+                            };
+                            let declared_payment_intervals = match self
+                                .decode_u32(event.data.get("declared_payment_intervals"))
+                            {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                        "SubscriptionExtended event failed to decode declared_payment_intervals: {}",
+                                        err
+                                    );
+                                    continue;
+                                }
+                            };
+                            log::info!(
+                            "Subscription for account: {:?} extended by {} intervals to {} declared intervals",
+                            for_account,
+                            intervals,
+                            declared_payment_intervals
                         );
 
-                        log::info!("New subscription for account: {:?}", for_account);
-                    }
-                    Some(n) if n == "CancelledSubscription" => {
-                        let for_account =
-                            match self.decode_account_id(event.data.get("for_account")) {
+                            // confirm the extension to the subscriber
+                            let channel_handles = match self.active_subscriptions.lock() {
+                                Ok(v) => v
+                                    .get(&for_account)
+                                    .map(|subscription| subscription.channel_handles.clone())
+                                    .unwrap_or_default(),
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            let msg = EventNotification {
+                                block: event_block,
+                                event_index,
+                                notification: SubscriptionExtendedNotification {
+                                    subscription_account: for_account,
+                                    intervals,
+                                    declared_payment_intervals,
+                                }
+                                .into(),
+                            };
+                            for channel_handle in channel_handles {
+                                outbox.enqueue(&ChannelHandle(channel_handle), &msg)?;
+                            }
+                        }
+                        Some(n) if n == "ChannelHandleUpdated" => {
+                            let for_account = match self
+                                .decode_account_id(event.data.get("for_account"))
+                            {
                                 Ok(v) => v,
                                 Err(err) => {
                                     log::error!(
-                                        "CancelSubscription event failed to decode for_account: {}",
+                                        "ChannelHandleUpdated event failed to decode for_account: {}",
                                         err
                                     );
                                     continue;
                                 }
                             };
-                        let mut active_subscriptions = match self.active_subscriptions.lock() {
-                            Ok(v) => v,
-                            Err(err) => {
-                                log::error!("Unable to lock active_subscriptions: {:?}", err);
+                            let old_handle = match self.decode_string(event.data.get("old_handle"))
+                            {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                    "ChannelHandleUpdated event failed to decode old_handle: {}",
+                                    err
+                                );
+                                    continue;
+                                }
+                            };
+                            let new_handle = match self.decode_string(event.data.get("new_handle"))
+                            {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!(
+                                    "ChannelHandleUpdated event failed to decode new_handle: {}",
+                                    err
+                                );
+                                    continue;
+                                }
+                            };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            let channel_handle = active_subscriptions
+                                .get_mut(&for_account)
+                                .and_then(|subscription| {
+                                    subscription
+                                        .channel_handles
+                                        .iter_mut()
+                                        .find(|channel_handle| **channel_handle == old_handle)
+                                });
+                            match channel_handle {
+                                Some(channel_handle) => *channel_handle = new_handle,
+                                None => {
+                                    log::warn!(
+                                    "Channel handle updated for unknown subscription or channel handle: {:?}",
+                                    for_account
+                                );
+                                    continue;
+                                }
+                            }
+
+                            log::info!("Updated channel handle for account: {:?}", for_account);
+                        }
+                        Some(n) if n == "SubscriptionPaused" || n == "SubscriptionResumed" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                            "{} event failed to decode for_account: {}",
+                                            n,
+                                            err
+                                        );
+                                        continue;
+                                    }
+                                };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            match active_subscriptions.get_mut(&for_account) {
+                                Some(subscription) => {
+                                    subscription.paused = n == "SubscriptionPaused"
+                                }
+                                None => {
+                                    log::warn!(
+                                    "Subscription paused or resumed for unknown subscription: {:?}",
+                                    for_account
+                                );
+                                    continue;
+                                }
+                            }
+
+                            log::info!("{} for account: {:?}", n, for_account);
+                        }
+                        Some(n) if n == "EventKindsUpdated" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "EventKindsUpdated event failed to decode for_account: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+                            let event_kinds =
+                                match self.decode_event_kinds(event.data.get("event_kinds")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "EventKindsUpdated event failed to decode event_kinds: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            match active_subscriptions.get_mut(&for_account) {
+                                Some(subscription) => subscription.event_kinds = event_kinds,
+                                None => {
+                                    log::warn!(
+                                        "Event kinds updated for unknown subscription: {:?}",
+                                        for_account
+                                    );
+                                    continue;
+                                }
+                            }
+
+                            log::info!("Updated event kinds for account: {:?}", for_account);
+                        }
+                        Some(n) if n == "MinAmountsUpdated" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "MinAmountsUpdated event failed to decode for_account: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+                            let min_amounts =
+                                match self.decode_min_amounts(event.data.get("min_amounts")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "MinAmountsUpdated event failed to decode min_amounts: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            match active_subscriptions.get_mut(&for_account) {
+                                Some(subscription) => subscription.min_amounts = min_amounts,
+                                None => {
+                                    log::warn!(
+                                        "Minimum amounts updated for unknown subscription: {:?}",
+                                        for_account
+                                    );
+                                    continue;
+                                }
+                            }
+
+                            log::info!("Updated minimum amounts for account: {:?}", for_account);
+                        }
+                        Some(n) if n == "WatchedAccountAdded" || n == "WatchedAccountRemoved" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                            "{} event failed to decode for_account: {}",
+                                            n,
+                                            err
+                                        );
+                                        continue;
+                                    }
+                                };
+                            let account = match self.decode_account_id(event.data.get("account")) {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("{} event failed to decode account: {}", n, err);
+                                    continue;
+                                }
+                            };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            let updated = if n == "WatchedAccountAdded" {
+                                active_subscriptions.add_watched_account(&for_account, account)
+                            } else {
+                                active_subscriptions.remove_watched_account(&for_account, &account)
+                            };
+                            if !updated {
+                                log::warn!(
+                                    "Watched accounts updated for unknown subscription: {:?}",
+                                    for_account
+                                );
                                 continue;
                             }
-                        };
-                        active_subscriptions.remove(&for_account);
 
-                        log::info!("Cancelled subscription for account: {:?}", for_account);
-                    }
-                    Some(n) if n == "CancelledSubscriptions" => {
-                        let for_accounts =
-                            match self.decode_account_ids(event.data.get("for_accounts")) {
+                            log::info!("Updated watched accounts for account: {:?}", for_account);
+                        }
+                        Some(n) if n == "CancelledSubscription" => {
+                            let for_account =
+                                match self.decode_account_id(event.data.get("for_account")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
+                                        "CancelSubscription event failed to decode for_account: {}",
+                                        err
+                                    );
+                                        continue;
+                                    }
+                                };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
                                 Ok(v) => v,
                                 Err(err) => {
-                                    log::error!(
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
+                                    continue;
+                                }
+                            };
+                            active_subscriptions.remove(&for_account);
+
+                            log::info!("Cancelled subscription for account: {:?}", for_account);
+                        }
+                        Some(n) if n == "CancelledSubscriptions" => {
+                            let for_accounts =
+                                match self.decode_account_ids(event.data.get("for_accounts")) {
+                                    Ok(v) => v,
+                                    Err(err) => {
+                                        log::error!(
                                     "CancelSubscriptions event failed to decode for_accounts: {}",
                                     err
                                 );
+                                        continue;
+                                    }
+                                };
+                            let mut active_subscriptions = match self.active_subscriptions.lock() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    log::error!("Unable to lock active_subscriptions: {:?}", err);
                                     continue;
                                 }
                             };
-                        let mut active_subscriptions = match self.active_subscriptions.lock() {
-                            Ok(v) => v,
-                            Err(err) => {
-                                log::error!("Unable to lock active_subscriptions: {:?}", err);
-                                continue;
+                            for for_account in for_accounts.iter() {
+                                active_subscriptions.remove(for_account);
                             }
-                        };
-                        for for_account in for_accounts.iter() {
-                            active_subscriptions.remove(for_account);
                         }
-                    }
-                    Some(n) => {
-                        log::warn!("Not matched smart contract event name: {}", n);
-                        continue;
-                    }
-                    None => {
-                        log::warn!("Undefined smart contract event name");
-                        continue;
-                    }
-                };
+                        Some(n) => {
+                            log::warn!("Not matched smart contract event name: {}", n);
+                            continue;
+                        }
+                        None => {
+                            log::warn!("Undefined smart contract event name");
+                            continue;
+                        }
+                    };
+                }
             }
+            self.store
+                .save_cursor(SUBSCRIPTIONS_CURSOR, &Cursor::from(&block))?;
         }
-        bail!("No more blocks to proceed")
+        bail!("Subscriptions smart contract event loop terminated")
     }
 
     fn decode_account_id(&self, v: Option<&contract_transcode::Value>) -> Result<AccountId> {
@@ -277,6 +833,16 @@ impl Subscriptions {
         }
     }
 
+    fn decode_u32(&self, v: Option<&contract_transcode::Value>) -> Result<u32> {
+        match v {
+            Some(v) => {
+                let value: u128 = ConvertibleValue(v.clone()).try_into()?;
+                Ok(u32::try_from(value)?)
+            }
+            None => bail!("missing attribute of type u32"),
+        }
+    }
+
     fn decode_string(&self, v: Option<&contract_transcode::Value>) -> Result<String> {
         match v {
             Some(v) => ConvertibleValue(v.clone()).try_into(),
@@ -284,6 +850,30 @@ impl Subscriptions {
         }
     }
 
+    fn decode_strings(&self, v: Option<&contract_transcode::Value>) -> Result<Vec<String>> {
+        match v {
+            Some(v) => ConvertibleValue(v.clone()).try_into(),
+            None => bail!("missing attribute of type Seq<string>"),
+        }
+    }
+
+    fn decode_event_kinds(&self, v: Option<&contract_transcode::Value>) -> Result<Vec<EventKind>> {
+        match v {
+            Some(v) => decode_event_kinds(v),
+            None => bail!("missing attribute of type Seq<EventKind>"),
+        }
+    }
+
+    fn decode_min_amounts(
+        &self,
+        v: Option<&contract_transcode::Value>,
+    ) -> Result<HashMap<EventKind, Balance>> {
+        match v {
+            Some(v) => decode_min_amounts(v),
+            None => bail!("missing attribute of type Seq<(EventKind, Balance)>"),
+        }
+    }
+
     fn decode_account_ids(&self, v: Option<&contract_transcode::Value>) -> Result<Vec<AccountId>> {
         let res: Result<Vec<Subscription>> = match v {
             Some(v) => ConvertibleValue(v.clone()).try_into(),
@@ -295,3 +885,165 @@ impl Subscriptions {
         }
     }
 }
+
+/// Decodes the list of `EventKind` variants of the smart contract.
+/// Kinds not supported by the service are skipped
+fn decode_event_kinds(value: &contract_transcode::Value) -> Result<Vec<EventKind>> {
+    let elems = match value {
+        contract_transcode::Value::Seq(seq) => seq.elems(),
+        _ => bail!(
+            "Failed parsing `event_kinds`. Expected `Seq(_)` but instead got: {:?}",
+            value
+        ),
+    };
+
+    let mut event_kinds = Vec::with_capacity(elems.len());
+    for elem in elems {
+        if let Some(kind) = decode_event_kind(elem)? {
+            event_kinds.push(kind);
+        }
+    }
+    Ok(event_kinds)
+}
+
+/// Decodes the list of minimum amounts per `EventKind` of the smart contract.
+/// Kinds not supported by the service are skipped
+fn decode_min_amounts(value: &contract_transcode::Value) -> Result<HashMap<EventKind, Balance>> {
+    let elems = match value {
+        contract_transcode::Value::Seq(seq) => seq.elems(),
+        _ => bail!(
+            "Failed parsing `min_amounts`. Expected `Seq(_)` but instead got: {:?}",
+            value
+        ),
+    };
+
+    let mut min_amounts = HashMap::with_capacity(elems.len());
+    for elem in elems {
+        let (kind, amount) = match elem {
+            contract_transcode::Value::Tuple(tuple) => {
+                let values = tuple.values().collect::<Vec<_>>();
+                match values[..] {
+                    [kind, amount] => (kind, amount),
+                    _ => bail!("Expected `(EventKind, Balance)` but got: {:?}", elem),
+                }
+            }
+            _ => bail!("Expected `(EventKind, Balance)` but got: {:?}", elem),
+        };
+        let amount: Balance = ConvertibleValue(amount.clone()).try_into()?;
+        if let Some(kind) = decode_event_kind(kind)? {
+            min_amounts.insert(kind, amount);
+        }
+    }
+    Ok(min_amounts)
+}
+
+/// Decodes a single `EventKind` variant of the smart contract, `None` if not supported by the service
+fn decode_event_kind(value: &contract_transcode::Value) -> Result<Option<EventKind>> {
+    let name = match value {
+        contract_transcode::Value::Tuple(variant) => variant.ident(),
+        _ => None,
+    }
+    .ok_or_else(|| anyhow!("Expected `EventKind` variant but got: {:?}", value))?;
+
+    let kind = EventKind::from_variant_name(&name);
+    if kind.is_none() {
+        log::debug!("Skipping event kind not supported by the service: {}", name);
+    }
+    Ok(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::from([byte; 32])
+    }
+
+    fn subscription(for_account: u8, watched_accounts: &[u8]) -> Subscription {
+        Subscription {
+            for_account: account(for_account),
+            channel_handles: vec![format!("chat_id:{}", for_account)],
+            event_kinds: vec![EventKind::TransferIn],
+            min_amounts: HashMap::new(),
+            watched_accounts: watched_accounts.iter().map(|b| account(*b)).collect(),
+            paused: false,
+        }
+    }
+
+    fn watchers_of(active_subscriptions: &ActiveSubscriptions, byte: u8) -> HashSet<AccountId> {
+        active_subscriptions
+            .watching(&account(byte))
+            .map(|s| s.for_account.clone())
+            .collect()
+    }
+
+    #[test]
+    fn watching_matches_every_subscription_watching_account() {
+        let mut active_subscriptions = ActiveSubscriptions::default();
+        active_subscriptions.insert(subscription(1, &[3]));
+        active_subscriptions.insert(subscription(2, &[]));
+        assert!(active_subscriptions.add_watched_account(&account(2), account(3)));
+
+        assert_eq!(
+            watchers_of(&active_subscriptions, 3),
+            HashSet::from([account(1), account(2)])
+        );
+        assert_eq!(
+            watchers_of(&active_subscriptions, 1),
+            HashSet::from([account(1)])
+        );
+        assert!(watchers_of(&active_subscriptions, 4).is_empty());
+    }
+
+    #[test]
+    fn remove_watched_account_clears_index() {
+        let mut active_subscriptions = ActiveSubscriptions::default();
+        active_subscriptions.insert(subscription(1, &[3]));
+        active_subscriptions.insert(subscription(2, &[3]));
+
+        assert!(active_subscriptions.remove_watched_account(&account(1), &account(3)));
+        assert_eq!(
+            watchers_of(&active_subscriptions, 3),
+            HashSet::from([account(2)])
+        );
+        assert!(active_subscriptions.remove_watched_account(&account(2), &account(3)));
+        assert!(watchers_of(&active_subscriptions, 3).is_empty());
+        assert!(!active_subscriptions.watchers.contains_key(&account(3)));
+
+        assert!(!active_subscriptions.remove_watched_account(&account(4), &account(3)));
+        assert!(!active_subscriptions.add_watched_account(&account(4), account(3)));
+    }
+
+    #[test]
+    fn remove_clears_index() {
+        let mut active_subscriptions = ActiveSubscriptions::default();
+        active_subscriptions.insert(subscription(1, &[3]));
+        active_subscriptions.insert(subscription(2, &[3]));
+
+        assert!(active_subscriptions.remove(&account(1)).is_some());
+        assert!(watchers_of(&active_subscriptions, 1).is_empty());
+        assert_eq!(
+            watchers_of(&active_subscriptions, 3),
+            HashSet::from([account(2)])
+        );
+
+        assert!(active_subscriptions.remove(&account(2)).is_some());
+        assert!(active_subscriptions.watchers.is_empty());
+        assert_eq!(active_subscriptions.values().count(), 0);
+    }
+
+    #[test]
+    fn insert_replaces_watched_accounts() {
+        let mut active_subscriptions = ActiveSubscriptions::default();
+        active_subscriptions.insert(subscription(1, &[3]));
+        active_subscriptions.insert(subscription(1, &[4]));
+
+        assert!(watchers_of(&active_subscriptions, 3).is_empty());
+        assert_eq!(
+            watchers_of(&active_subscriptions, 4),
+            HashSet::from([account(1)])
+        );
+        assert_eq!(active_subscriptions.values().count(), 1);
+    }
+}